@@ -0,0 +1,122 @@
+use crate::{
+    Ship, Star, Transform,
+    input::{Inputs, Player},
+    kinematics::KinematicsSet,
+    ray_circle_intersection,
+    ship::Energy,
+    weapon::{Hit, Owner},
+};
+use bevy::prelude::*;
+
+const RANGE: f32 = 2000.0;
+const ENERGY_COST: f32 = 40.0;
+const DAMAGE: f32 = 25.0;
+
+/// seconds the beam stays on screen after firing
+const FLASH_DURATION: f32 = 0.15;
+
+/// A beam that has already been fired, kept around just long enough to be seen.
+#[derive(Clone, Debug, Component)]
+pub struct Beam {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub age: f32,
+}
+
+fn fire_beam(
+    mut commands: Commands,
+    mut ships: Query<(Entity, &Player, &Transform, &mut Energy), With<Ship>>,
+    stars: Query<(&Star, &Transform)>,
+    targets: Query<(Entity, &Transform), With<Ship>>,
+    inputs: Res<Inputs>,
+    mut hits: EventWriter<Hit>,
+) {
+    for (id, player, transform, mut energy) in ships.iter_mut() {
+        if !inputs.get(*player).fire_beam || energy.0 < ENERGY_COST {
+            continue;
+        }
+        energy.0 -= ENERGY_COST;
+
+        let origin = transform.translation;
+        let direction = transform.local_x();
+
+        // stars block line of sight, so the beam stops at the nearest one
+        let range = stars
+            .iter()
+            .filter_map(|(star, star_transform)| {
+                ray_circle_intersection(origin, direction, star_transform.translation, star.radius)
+            })
+            .fold(RANGE, f32::min);
+
+        let target = targets
+            .iter()
+            .filter(|(target, _)| *target != id)
+            .filter_map(|(target, target_transform)| {
+                ray_circle_intersection(
+                    origin,
+                    direction,
+                    target_transform.translation,
+                    Ship::HIT_RADIUS,
+                )
+                .map(|distance| (target, distance))
+            })
+            .filter(|(_, distance)| *distance < range)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        let length = match target {
+            Some((target, distance)) => {
+                hits.write(Hit {
+                    target,
+                    damage: DAMAGE,
+                });
+                distance
+            }
+            None => range,
+        };
+
+        commands.spawn((
+            Beam {
+                start: origin,
+                end: origin + direction * length,
+                age: 0.0,
+            },
+            Owner(id),
+        ));
+    }
+}
+
+fn age_beams(mut commands: Commands, mut beams: Query<(Entity, &mut Beam)>, time: Res<Time>) {
+    for (id, mut beam) in beams.iter_mut() {
+        if beam.age > FLASH_DURATION {
+            commands.entity(id).despawn();
+            continue;
+        }
+
+        beam.age += time.delta_secs();
+    }
+}
+
+fn draw_beams(beams: Query<&Beam>, mut gizmos: Gizmos) {
+    for beam in beams.iter() {
+        gizmos.line_2d(
+            beam.start,
+            beam.end,
+            Color::oklch(0.85, 0.2, 200.0).with_alpha(1.0 - beam.age / FLASH_DURATION),
+        );
+    }
+}
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Hit>()
+            .init_resource::<Inputs>()
+            .add_systems(FixedUpdate, fire_beam.after(KinematicsSet))
+            .add_systems(PostUpdate, age_beams);
+
+        if crate::has_renderer(app) {
+            app.add_systems(PostUpdate, draw_beams.before(age_beams));
+        }
+    }
+}
@@ -0,0 +1,30 @@
+#[cfg(debug_assertions)]
+#[allow(unused_imports, clippy::single_component_path_imports)]
+use bevy_dylib;
+
+use bevy::prelude::*;
+use spacewar::{HeadlessPlugins, headless};
+use std::process::ExitCode;
+
+const DEFAULT_TICKS: u32 = 64 * 60;
+
+fn main() -> ExitCode {
+    let ticks = match std::env::args().nth(1).map(|arg| arg.parse()) {
+        None => DEFAULT_TICKS,
+        Some(Ok(ticks)) => ticks,
+        Some(Err(err)) => {
+            eprintln!("usage: spacewar-headless [TICKS]: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugins::default());
+    headless::run_ticks(&mut app, ticks);
+
+    for line in headless::ship_summary(app.world_mut()) {
+        println!("{line}");
+    }
+
+    ExitCode::SUCCESS
+}
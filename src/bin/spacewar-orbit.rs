@@ -0,0 +1,444 @@
+#[cfg(debug_assertions)]
+#[allow(unused_imports, clippy::single_component_path_imports)]
+use bevy_dylib;
+
+use bevy::math::Vec2;
+use clap::{CommandFactory, Parser, ValueEnum, error::ErrorKind};
+use serde::{Deserialize, Serialize};
+use spacewar::{
+    GravityField, Mass, TrajectoryNode, gravity::GRAVITATIONAL_CONSTANT, kepler::Orbit,
+};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+/// How long to follow trajectories that never come back, by default.
+const ESCAPE_DURATION: f64 = 600.0;
+
+/// Most integration steps a trajectory is followed for, a few days' worth at the default
+/// timestep, so that nearly parabolic orbits don't take forever to come round.
+const MAX_STEPS: f64 = 16_777_216.0;
+
+/// Works out where something goes under the pull of some masses, without starting the game.
+///
+/// Prints the orbit around whichever mass pulls hardest at the start, along with the trajectory
+/// integrated the same way the game predicts it.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// A mass pulling on things, in kilograms, at the origin unless placed elsewhere
+    #[arg(
+        long = "mass",
+        value_name = "KG[@X,Y]",
+        value_parser = mass,
+        required_unless_present = "json",
+        allow_hyphen_values = true,
+    )]
+    masses: Vec<Mass>,
+
+    /// Where it starts
+    #[arg(
+        long,
+        value_name = "X,Y",
+        value_parser = vector,
+        required_unless_present = "json",
+        allow_hyphen_values = true
+    )]
+    position: Option<Vec2>,
+
+    /// How fast it starts out going
+    #[arg(
+        long,
+        value_name = "X,Y",
+        value_parser = vector,
+        default_value = "0,0",
+        allow_hyphen_values = true
+    )]
+    velocity: Vec2,
+
+    #[arg(long, default_value_t = GRAVITATIONAL_CONSTANT)]
+    gravitational_constant: f32,
+
+    /// Read the masses and starting node from a JSON file instead, or standard input for `-`
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["masses", "position", "velocity", "gravitational_constant"],
+    )]
+    json: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+
+    /// How many points along the trajectory to print, after the one it starts at
+    #[arg(long, default_value_t = 100)]
+    samples: usize,
+
+    /// How long to follow the trajectory for, by default one orbit or ten minutes if it escapes
+    #[arg(long, value_name = "SECONDS", value_parser = positive::<f64>)]
+    duration: Option<f64>,
+
+    /// Integration step, the same as a fixed tick by default
+    #[arg(long, value_name = "SECONDS", default_value_t = 1.0 / 64.0, value_parser = positive::<f32>)]
+    timestep: f32,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    /// the orbit as `#` comments, then a row per sample
+    Csv,
+    Json,
+}
+
+/// What `--json` reads.
+#[derive(Debug, Deserialize)]
+struct Input {
+    #[serde(default = "default_gravitational_constant")]
+    gravitational_constant: f32,
+    masses: Vec<Mass>,
+    start: TrajectoryNode,
+}
+
+fn default_gravitational_constant() -> f32 {
+    GRAVITATIONAL_CONSTANT
+}
+
+/// The same elements [`Placement::Elements`](spacewar::scenario::Placement::Elements) takes,
+/// so they can be pasted into a scenario.
+#[derive(Debug, Serialize)]
+struct Elements {
+    semi_major_axis: f64,
+    eccentricity: f64,
+    /// in degrees
+    argument_of_periapsis: f64,
+    /// in degrees
+    true_anomaly: f64,
+    prograde: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Sample {
+    time: f64,
+    position: Vec2,
+    velocity: Vec2,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    /// index of the mass being orbited
+    around: usize,
+    /// missing for parabolic orbits and straight falls, which have none worth printing
+    elements: Option<Elements>,
+    periapsis: Option<f64>,
+    apoapsis: Option<f64>,
+    period: Option<f64>,
+    trajectory: Vec<Sample>,
+}
+
+fn vector(arg: &str) -> Result<Vec2, String> {
+    let (x, y) = arg
+        .split_once(',')
+        .ok_or_else(|| "expected X,Y".to_string())?;
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<f32>()
+            .map_err(|err| format!("{s:?}: {err}"))
+    };
+    Ok(Vec2::new(parse(x)?, parse(y)?))
+}
+
+fn mass(arg: &str) -> Result<Mass, String> {
+    let (mass, translation) = match arg.split_once('@') {
+        Some((mass, translation)) => (mass, vector(translation)?),
+        None => (arg, Vec2::ZERO),
+    };
+
+    match mass.trim().parse::<f32>() {
+        Ok(mass) if mass > 0.0 => Ok(Mass { translation, mass }),
+        Ok(_) => Err("has to be more than 0".into()),
+        Err(err) => Err(format!("{mass:?}: {err}")),
+    }
+}
+
+fn positive<T: std::str::FromStr<Err: std::fmt::Display> + PartialOrd + Default>(
+    arg: &str,
+) -> Result<T, String> {
+    match arg.parse::<T>() {
+        Ok(value) if value > T::default() => Ok(value),
+        Ok(_) => Err("has to be more than 0".into()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+impl Input {
+    fn read(path: &Path) -> Result<Self, String> {
+        let mut text = String::new();
+        let read = if path.as_os_str() == "-" {
+            std::io::stdin().read_to_string(&mut text)
+        } else {
+            std::fs::File::open(path).and_then(|mut file| file.read_to_string(&mut text))
+        };
+        read.map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+
+        serde_json::from_str(&text)
+            .map_err(|err| format!("couldn't parse {}: {err}", path.display()))
+    }
+}
+
+fn report(
+    input: &Input,
+    samples: usize,
+    duration: Option<f64>,
+    timestep: f32,
+) -> Result<Report, String> {
+    let mut gravity: GravityField = input.masses.iter().copied().collect();
+    gravity.set_gravitational_constant(input.gravitational_constant);
+
+    let start = input.start;
+    let (around, mass) = input
+        .masses
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| {
+            let pull =
+                |mass: &Mass| mass.mass / start.translation().distance_squared(mass.translation);
+            pull(a).total_cmp(&pull(b))
+        })
+        .expect("there's always at least one mass");
+
+    let orbit = Orbit::from_state(
+        start.translation() - mass.translation,
+        start.velocity(),
+        input.gravitational_constant * mass.mass,
+    );
+
+    let period = orbit.and_then(|orbit| orbit.period());
+    let steps = (duration.or(period).unwrap_or(ESCAPE_DURATION) / timestep as f64)
+        .ceil()
+        .max(1.0);
+    if steps > MAX_STEPS {
+        return Err(match duration {
+            Some(duration) => format!(
+                "following it for {duration} s would take {steps} steps, more than {MAX_STEPS}; \
+                 pass a shorter --duration or a longer --timestep"
+            ),
+            None => format!(
+                "it takes {} s to come round, which would take {steps} steps to follow, more than \
+                 {MAX_STEPS}; pass --duration to follow it for less",
+                period.unwrap_or_default()
+            ),
+        });
+    }
+    let steps = steps as usize;
+    let samples = samples.clamp(1, steps);
+
+    let sample = |step: usize, node: TrajectoryNode| Sample {
+        time: step as f64 * timestep as f64,
+        position: node.translation(),
+        velocity: node.velocity(),
+    };
+    let mut trajectory = vec![sample(0, start)];
+    for (step, node) in (1..=steps).zip(gravity.trajectory_starting_at(start, timestep)) {
+        // evenly spread, with the last one right at the end
+        if step * samples >= trajectory.len() * steps {
+            trajectory.push(sample(step, node));
+        }
+    }
+
+    Ok(Report {
+        around,
+        elements: orbit.map(|orbit| Elements {
+            semi_major_axis: orbit.semi_major_axis,
+            eccentricity: orbit.eccentricity,
+            argument_of_periapsis: orbit.argument_of_periapsis.to_degrees(),
+            true_anomaly: orbit.true_anomaly.to_degrees(),
+            prograde: orbit.prograde,
+        }),
+        periapsis: orbit.map(|orbit| orbit.periapsis()),
+        apoapsis: orbit.and_then(|orbit| orbit.apoapsis()),
+        period,
+        trajectory,
+    })
+}
+
+fn print_csv(report: &Report) {
+    println!("# around: {}", report.around);
+    if let Some(elements) = &report.elements {
+        println!("# semi_major_axis: {}", elements.semi_major_axis);
+        println!("# eccentricity: {}", elements.eccentricity);
+        println!(
+            "# argument_of_periapsis: {}",
+            elements.argument_of_periapsis
+        );
+        println!("# true_anomaly: {}", elements.true_anomaly);
+        println!("# prograde: {}", elements.prograde);
+    }
+    for (name, value) in [
+        ("periapsis", report.periapsis),
+        ("apoapsis", report.apoapsis),
+        ("period", report.period),
+    ] {
+        if let Some(value) = value {
+            println!("# {name}: {value}");
+        }
+    }
+
+    println!("time,x,y,vx,vy");
+    for sample in &report.trajectory {
+        println!(
+            "{},{},{},{},{}",
+            sample.time, sample.position.x, sample.position.y, sample.velocity.x, sample.velocity.y
+        );
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let input = match &args.json {
+        Some(path) => match Input::read(path) {
+            Ok(input) => input,
+            Err(err) => Args::command().error(ErrorKind::InvalidValue, err).exit(),
+        },
+        None => Input {
+            gravitational_constant: args.gravitational_constant,
+            masses: args.masses.clone(),
+            start: TrajectoryNode::from_translation_velocity(
+                args.position.unwrap_or_default(),
+                args.velocity,
+            ),
+        },
+    };
+    if input.masses.is_empty() {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "there has to be at least one mass",
+            )
+            .exit();
+    }
+
+    let report = match report(&input, args.samples, args.duration, args.timestep) {
+        Ok(report) => report,
+        Err(err) => Args::command().error(ErrorKind::InvalidValue, err).exit(),
+    };
+    match args.format {
+        Format::Csv => print_csv(&report),
+        Format::Json => match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("couldn't serialize report: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_are_consistent() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_masses() {
+        assert_eq!(
+            mass("1.6e16").unwrap(),
+            Mass {
+                translation: Vec2::ZERO,
+                mass: 1.6e16
+            }
+        );
+        assert_eq!(
+            mass("4e15@2500,-400").unwrap().translation,
+            Vec2::new(2500.0, -400.0)
+        );
+        assert!(mass("-1").is_err());
+        assert!(mass("1@2").is_err());
+    }
+
+    #[test]
+    fn circular_orbit_comes_back_around() {
+        const MASS: f32 = 1.6e18;
+        const RADIUS: f32 = 1000.0;
+
+        let speed = (GRAVITATIONAL_CONSTANT * MASS / RADIUS).sqrt();
+        let input = Input {
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+            masses: vec![Mass {
+                translation: Vec2::ZERO,
+                mass: MASS,
+            }],
+            start: TrajectoryNode::from_translation_velocity(
+                Vec2::new(RADIUS, 0.0),
+                Vec2::new(0.0, speed),
+            ),
+        };
+        let report = report(&input, 50, None, 1.0 / 64.0).unwrap();
+
+        let elements = report.elements.unwrap();
+        assert!(elements.eccentricity < 1e-3, "{}", elements.eccentricity);
+        assert!(elements.prograde);
+
+        let period = std::f64::consts::TAU * RADIUS as f64 / speed as f64;
+        assert!((report.period.unwrap() - period).abs() < period * 1e-3);
+
+        assert_eq!(report.trajectory.len(), 51);
+        for sample in &report.trajectory {
+            let radius = sample.position.length();
+            assert!(
+                (radius - RADIUS).abs() < RADIUS * 0.01,
+                "drifted to {radius}"
+            );
+        }
+        let end = report.trajectory.last().unwrap().position;
+        assert!(
+            end.distance(Vec2::new(RADIUS, 0.0)) < RADIUS * 0.05,
+            "ended at {end}"
+        );
+    }
+
+    #[test]
+    fn refuses_orbits_too_long_to_follow() {
+        const MASS: f32 = 1.6e18;
+        const RADIUS: f32 = 1000.0;
+
+        // only just short of escaping
+        let speed = (2.0 * GRAVITATIONAL_CONSTANT * MASS / RADIUS).sqrt() * 0.999_999;
+        let input = Input {
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+            masses: vec![Mass {
+                translation: Vec2::ZERO,
+                mass: MASS,
+            }],
+            start: TrajectoryNode::from_translation_velocity(
+                Vec2::new(RADIUS, 0.0),
+                Vec2::new(0.0, speed),
+            ),
+        };
+
+        let err = report(&input, 50, None, 1.0 / 64.0).unwrap_err();
+        assert!(err.contains("--duration"), "{err}");
+        assert!(report(&input, 50, Some(60.0), 1.0 / 64.0).is_ok());
+    }
+
+    #[test]
+    fn reads_json() {
+        let input: Input = serde_json::from_str(
+            r#"{
+                "masses": [{ "translation": [0.0, 0.0], "mass": 1.6e16 }],
+                "start": { "translation": [-1000.0, 500.0], "velocity": [50.0, 0.0] }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(input.gravitational_constant, GRAVITATIONAL_CONSTANT);
+        assert_eq!(input.start.velocity(), Vec2::new(50.0, 0.0));
+    }
+}
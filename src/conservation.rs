@@ -0,0 +1,116 @@
+use crate::{GravityField, Transform2d, Velocity, kinematics::KinematicsSet};
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsPlugin, RegisterDiagnostic},
+    prelude::*,
+};
+
+/// Worst relative drift in specific orbital energy of any body, in percent.
+pub const ENERGY_DRIFT: DiagnosticPath = DiagnosticPath::const_new("conservation/energy_drift");
+
+/// Worst relative drift in specific angular momentum of any body, in percent.
+pub const ANGULAR_MOMENTUM_DRIFT: DiagnosticPath =
+    DiagnosticPath::const_new("conservation/angular_momentum_drift");
+
+/// Quantities that should stay constant for a body coasting through the [`GravityField`], and how
+/// far integration has pushed them so far.
+///
+/// Only the change across each integration step counts as drift, so thrusting and other
+/// deliberate changes in velocity don't show up in it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component)]
+pub struct OrbitalInvariants {
+    /// kinetic plus potential energy per unit mass
+    pub energy: f32,
+    /// per unit mass, about the field's barycenter
+    pub angular_momentum: f32,
+    /// accumulated relative change in `energy`
+    pub energy_drift: f32,
+    /// accumulated relative change in `angular_momentum`
+    pub angular_momentum_drift: f32,
+}
+
+impl OrbitalInvariants {
+    fn measure(&mut self, translation: Vec2, velocity: Vec2, gravity: &GravityField) {
+        self.energy = velocity.length_squared() / 2.0 + gravity.potential_at(translation);
+        self.angular_momentum = (translation - gravity.barycenter()).perp_dot(velocity);
+    }
+}
+
+/// Relative change from `before` to `after`, or nothing if `before` is too close to zero for
+/// that to mean anything.
+fn relative_change(before: f32, after: f32) -> f32 {
+    if before.abs() < f32::EPSILON {
+        0.0
+    } else {
+        (after - before) / before.abs()
+    }
+}
+
+fn measure_before(
+    mut commands: Commands,
+    mut bodies: Query<(
+        Entity,
+        &Transform2d,
+        &Velocity,
+        Option<&mut OrbitalInvariants>,
+    )>,
+    gravity: Res<GravityField>,
+) {
+    for (entity, transform, velocity, invariants) in bodies.iter_mut() {
+        match invariants {
+            Some(mut invariants) => invariants.measure(transform.translation, velocity.0, &gravity),
+            None => {
+                let mut invariants = OrbitalInvariants::default();
+                invariants.measure(transform.translation, velocity.0, &gravity);
+                commands.entity(entity).insert(invariants);
+            }
+        }
+    }
+}
+
+fn measure_after(
+    mut bodies: Query<(&Transform2d, &Velocity, &mut OrbitalInvariants)>,
+    gravity: Res<GravityField>,
+    mut diagnostics: Diagnostics,
+) {
+    let mut worst_energy_drift: f32 = 0.0;
+    let mut worst_angular_momentum_drift: f32 = 0.0;
+
+    for (transform, velocity, mut invariants) in bodies.iter_mut() {
+        let before = *invariants;
+        invariants.measure(transform.translation, velocity.0, &gravity);
+
+        invariants.energy_drift += relative_change(before.energy, invariants.energy);
+        invariants.angular_momentum_drift +=
+            relative_change(before.angular_momentum, invariants.angular_momentum);
+
+        worst_energy_drift = worst_energy_drift.max(invariants.energy_drift.abs());
+        worst_angular_momentum_drift =
+            worst_angular_momentum_drift.max(invariants.angular_momentum_drift.abs());
+    }
+
+    diagnostics.add_measurement(&ENERGY_DRIFT, || worst_energy_drift as f64 * 100.0);
+    diagnostics.add_measurement(&ANGULAR_MOMENTUM_DRIFT, || {
+        worst_angular_momentum_drift as f64 * 100.0
+    });
+}
+
+/// Measures how well energy and angular momentum are conserved by integration, every tick.
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<DiagnosticsPlugin>() {
+            app.add_plugins(DiagnosticsPlugin);
+        }
+
+        app.register_diagnostic(Diagnostic::new(ENERGY_DRIFT).with_suffix("%"))
+            .register_diagnostic(Diagnostic::new(ANGULAR_MOMENTUM_DRIFT).with_suffix("%"))
+            .add_systems(
+                FixedUpdate,
+                (
+                    measure_before.before(KinematicsSet),
+                    measure_after.after(KinematicsSet),
+                ),
+            );
+    }
+}
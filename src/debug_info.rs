@@ -1,6 +1,6 @@
-use bevy::prelude::*;
+use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*};
 
-use crate::KeyBinds;
+use crate::{KeyBinds, conservation};
 
 /*
 macro_rules! line_enum {
@@ -35,11 +35,23 @@ line_enum! {
 } */
 
 #[derive(Debug, Clone, Default)]
-pub struct Plugin;
+pub struct Plugin {
+    /// whether the overlay starts out shown, until toggled
+    pub state: State,
+}
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<State>()
+        if !crate::has_renderer(app) {
+            return;
+        }
+
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+
+        app.insert_state(self.state)
+            .init_resource::<crate::input::LocalPlayer>()
             .add_systems(Startup, (spawn, post_spawn).chain())
             .add_systems(
                 Update,
@@ -89,6 +101,8 @@ pub enum Line2 {
     Zoom(f32),
     Fps(f32),
     Distance(f32),
+    EnergyDrift(f32),
+    AngularMomentumDrift(f32),
 }
 
 impl Line2 {
@@ -98,6 +112,8 @@ impl Line2 {
     const ZOOM: Self = Self::Zoom(1.0);
     const FPS: Self = Self::Fps(0.0);
     const DISTANCE: Self = Self::Distance(0.0);
+    const ENERGY_DRIFT: Self = Self::EnergyDrift(0.0);
+    const ANGULAR_MOMENTUM_DRIFT: Self = Self::AngularMomentumDrift(0.0);
 
     pub const fn field(&self) -> &'static str {
         match self {
@@ -107,6 +123,8 @@ impl Line2 {
             Self::Zoom(_) => "Zoom",
             Self::Fps(_) => "Frames per second",
             Self::Distance(_) => "Distance from origin",
+            Self::EnergyDrift(_) => "Energy drift",
+            Self::AngularMomentumDrift(_) => "Angular momentum drift",
         }
     }
 
@@ -118,6 +136,7 @@ impl Line2 {
             Self::Zoom(v) => format!("{v:.2}x"),
             Self::Fps(v) => format!("{v:.2}"),
             Self::Distance(v) => format!("{v:.2}m"),
+            Self::EnergyDrift(v) | Self::AngularMomentumDrift(v) => format!("{v:.4}%"),
         }
     }
 }
@@ -153,6 +172,8 @@ fn spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
         Line2::ZOOM,
         Line2::FPS,
         Line2::DISTANCE,
+        Line2::ENERGY_DRIFT,
+        Line2::ANGULAR_MOMENTUM_DRIFT,
     ];
 
     commands
@@ -245,7 +266,7 @@ fn post_spawn(
             Line::Fps => format!(
                 "{:.2}",
                 diagnostics
-                    .get(&bevy::diagnostic::FrameTimeDiagnosticsPlugin::FPS)
+                    .get(&FrameTimeDiagnosticsPlugin::FPS)
                     .and_then(|d| d.average())
                     .unwrap_or_default()
             ),
@@ -256,11 +277,21 @@ fn post_spawn(
 fn update2(
     lines: Query<(&mut Line2, &Children)>,
     mut spans: Query<&mut TextSpan>,
-    ship: Single<(&crate::Ship, &crate::Transform)>,
+    ships: Query<(
+        &crate::Ship,
+        &crate::Transform,
+        &crate::Velocity,
+        &crate::input::Player,
+    )>,
+    local: Res<crate::input::LocalPlayer>,
     camera: Single<&Projection, With<Camera2d>>,
     diagnostics: Res<bevy::diagnostic::DiagnosticsStore>,
 ) {
-    let (ship, ship_transform) = ship.into_inner();
+    let Some((ship, ship_transform, velocity, _)) =
+        ships.iter().find(|(.., player)| **player == local.0)
+    else {
+        return;
+    };
     let projection = match **camera {
         Projection::Orthographic(ref projection) => projection,
         _ => unimplemented!(),
@@ -289,15 +320,27 @@ fn update2(
                 }
             }
             Line2::AngVel(ref mut angular_velocity) => *angular_velocity = ship.rotational_velocity,
-            Line2::Speed(ref mut speed) => *speed = ship.velocity.length(),
+            Line2::Speed(ref mut speed) => *speed = velocity.0.length(),
             Line2::Zoom(ref mut zoom) => *zoom = 1.0 / projection.scale,
             Line2::Fps(ref mut fps) => {
                 *fps = diagnostics
-                    .get(&bevy::diagnostic::FrameTimeDiagnosticsPlugin::FPS)
+                    .get(&FrameTimeDiagnosticsPlugin::FPS)
                     .and_then(|d| d.average())
                     .unwrap_or_default() as f32
             }
             Line2::Distance(ref mut distance) => *distance = ship_transform.translation.length(),
+            Line2::EnergyDrift(ref mut drift) => {
+                *drift = diagnostics
+                    .get(&conservation::ENERGY_DRIFT)
+                    .and_then(|d| d.value())
+                    .unwrap_or_default() as f32
+            }
+            Line2::AngularMomentumDrift(ref mut drift) => {
+                *drift = diagnostics
+                    .get(&conservation::ANGULAR_MOMENTUM_DRIFT)
+                    .and_then(|d| d.value())
+                    .unwrap_or_default() as f32
+            }
         }
     }
 }
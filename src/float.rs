@@ -0,0 +1,43 @@
+//! `f64` counterparts to [`bevy::math::ops`], for the few calculations done in double precision.
+//!
+//! Arithmetic and square roots are correctly rounded everywhere, but the standard library's
+//! trigonometry is only as good as whatever the platform provides, and can come out a bit
+//! differently from one machine to the next. With the `deterministic` feature these go through
+//! `libm` instead, as do Bevy's `ops` and glam's vector math, so the simulation gives the same bits
+//! on every platform.
+//!
+//! For the same reason nothing on the simulation's path uses `mul_add`, which rounds differently
+//! depending on whether the target has a fused multiply-add, and powers are written out as
+//! multiplications rather than left to `powi`.
+
+macro_rules! functions {
+    ($($name:ident($($arg:ident),+))*) => {
+        $(
+            #[inline]
+            pub fn $name($($arg: f64),+) -> f64 {
+                #[cfg(feature = "deterministic")]
+                {
+                    libm::$name($($arg),+)
+                }
+                #[cfg(not(feature = "deterministic"))]
+                {
+                    f64::$name($($arg),+)
+                }
+            }
+        )*
+    };
+}
+
+functions! {
+    sqrt(x)
+    sin(x)
+    cos(x)
+    tan(x)
+    atan(x)
+    atan2(y, x)
+    sinh(x)
+    cosh(x)
+    tanh(x)
+    asinh(x)
+    atanh(x)
+}
@@ -0,0 +1,171 @@
+use crate::{
+    Ship, Transform, Velocity,
+    kinematics::KinematicsSet,
+    weapon::{Hit, Weapon},
+};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// most fragments that can be flying around at once, oldest go first
+const MAX_LIVE: usize = 200;
+
+/// A piece of shrapnel or debris that keeps flying under gravity and hurts whatever it runs into.
+#[derive(Clone, Debug, PartialEq, Component, TypePath, Serialize, Deserialize)]
+pub struct Fragment {
+    pub damage: f32,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Default for Fragment {
+    fn default() -> Self {
+        Self {
+            damage: 10.0,
+            age: 0.0,
+            lifetime: Self::MAX_AGE,
+        }
+    }
+}
+
+impl Weapon for Fragment {
+    const MAX_AGE: f32 = 10.0;
+
+    fn age(&self) -> f32 {
+        self.age
+    }
+
+    fn age_mut(&mut self) -> &mut f32 {
+        &mut self.age
+    }
+
+    fn max_age(&self) -> f32 {
+        self.lifetime
+    }
+
+    fn color(&self) -> Color {
+        Color::oklch(0.8, 0.15, 60.0)
+    }
+}
+
+#[derive(Clone, Debug, Bundle, Default)]
+pub struct Bundle {
+    pub fragment: Fragment,
+    pub transform: Transform,
+    pub velocity: Velocity,
+}
+
+/// Bundles for `count` copies of `fragment` flying out of `origin` at `velocity`, each with up
+/// to `spread` added in a random direction.
+pub fn scatter(
+    origin: Vec2,
+    velocity: Vec2,
+    fragment: Fragment,
+    spread: f32,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Vec<Bundle> {
+    (0..count)
+        .map(|_| {
+            let direction = Rot2::radians(rng.gen_range(0.0..std::f32::consts::TAU)) * Vec2::X;
+            let speed = rng.gen_range(0.0..=spread);
+
+            Bundle {
+                fragment: fragment.clone(),
+                velocity: Velocity(velocity + direction * speed),
+                transform: Transform::default()
+                    .with_translation(origin)
+                    .with_rotation(Rot2::radians(direction.to_angle())),
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Resource, Bundle)]
+struct Sprite {
+    mesh: Mesh2d,
+    material: MeshMaterial2d<ColorMaterial>,
+}
+
+impl FromWorld for Sprite {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh: Mesh2d(world.add_asset(Triangle2d::new(
+                Vec2::new(-0.5, -0.4),
+                Vec2::new(0.6, 0.0),
+                Vec2::new(-0.3, 0.5),
+            ))),
+            material: MeshMaterial2d(world.add_asset(Color::WHITE)),
+        }
+    }
+}
+
+fn spawn(
+    mut commands: Commands,
+    fragments: Query<Entity, Added<Fragment>>,
+    mut components: Query<&mut Transform>,
+    sprite: Res<Sprite>,
+) {
+    for entity in fragments.iter() {
+        let mut transform = components.get_mut(entity).unwrap();
+
+        transform.scale = Vec2::splat(6.0);
+
+        commands.entity(entity).insert(sprite.clone());
+    }
+}
+
+fn impact(
+    mut commands: Commands,
+    fragments: Query<(Entity, &Fragment, &Transform)>,
+    ships: Query<(Entity, &Transform), With<Ship>>,
+    mut hits: EventWriter<Hit>,
+) {
+    for (id, fragment, fragment_transform) in fragments.iter() {
+        let Some((ship, _)) = ships.iter().find(|(_, transform)| {
+            transform
+                .translation
+                .distance(fragment_transform.translation)
+                < Ship::HIT_RADIUS
+        }) else {
+            continue;
+        };
+
+        hits.write(Hit {
+            target: ship,
+            damage: fragment.damage,
+        });
+
+        commands.entity(id).despawn();
+    }
+}
+
+fn enforce_cap(mut commands: Commands, fragments: Query<(Entity, &Fragment)>) {
+    let excess = fragments.iter().len().saturating_sub(MAX_LIVE);
+    if excess == 0 {
+        return;
+    }
+
+    let mut oldest = fragments.iter().collect::<Vec<_>>();
+    oldest.sort_by(|(_, a), (_, b)| b.age.total_cmp(&a.age));
+
+    for (id, _) in oldest.into_iter().take(excess) {
+        commands.entity(id).despawn();
+    }
+}
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(crate::weapon::Plugin::<Fragment>::default())
+            .add_systems(
+                FixedUpdate,
+                (impact, enforce_cap).chain().after(KinematicsSet),
+            );
+
+        if crate::has_renderer(app) {
+            app.init_resource::<Sprite>().add_systems(PostUpdate, spawn);
+        }
+    }
+}
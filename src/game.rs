@@ -0,0 +1,224 @@
+use crate::{
+    GravityField, KeyBinds, KeyPair, Ship, Star, Transform, Velocity, beam, conservation,
+    debug_info, fragment,
+    input::{self, Inputs, LocalPlayer, Player, Players},
+    kinematics, mine, missile, pause, replay, rewind,
+    rng::SimulationRng,
+    save,
+    scenario::{self, Placement, Scenario},
+    settings, ship, star, transform2d, turret, warp,
+};
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use std::ops::DerefMut;
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2d);
+}
+
+fn spawn_ship(commands: &mut Commands, scenario: &Scenario, player: Player) {
+    let spawn = scenario.spawn(player);
+    let mut ship = commands.spawn((
+        ship::Bundle {
+            transform: Transform::default().with_translation(spawn.position),
+            ship: Ship {
+                sas: None,
+                ..Default::default()
+            },
+            velocity: Velocity(spawn.velocity),
+        },
+        player,
+    ));
+
+    if scenario.rules.point_defense {
+        ship.insert(turret::PointDefense);
+    }
+}
+
+/// Sets everything up as the [`Scenario`] describes, as soon as there is one.
+pub(crate) fn start_match(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    players: Res<Players>,
+    gravity: Res<GravityField>,
+    mut camera: Query<(&mut bevy::prelude::Transform, &mut Projection), With<Camera2d>>,
+) {
+    commands.insert_resource(SimulationRng::seeded(scenario.seed));
+
+    let mut bodies: Vec<(Entity, Vec2)> = Vec::with_capacity(scenario.bodies.len());
+    for (index, body) in scenario.bodies.iter().enumerate() {
+        let orbit = scenario
+            .orbit(index, gravity.gravitational_constant())
+            .unwrap_or_else(|err| {
+                error!("{err}, so it stays put");
+                None
+            });
+
+        let position = match (&body.placement, orbit) {
+            (_, Some((parent, orbit))) => bodies[parent].1 + orbit.state().0,
+            (Placement::Fixed(position), None) => *position,
+            _ => Vec2::ZERO,
+        };
+
+        let mut entity = commands.spawn((
+            Name::new(body.name.clone()),
+            star::Bundle {
+                transform: Transform::default().with_translation(position),
+                star: Star {
+                    mass: body.mass,
+                    radius: body.radius,
+                },
+            },
+        ));
+        if let Some((parent, orbit)) = orbit {
+            entity.insert(star::Orbiting {
+                around: bodies[parent].0,
+                orbit,
+            });
+        }
+
+        bodies.push((entity.id(), position));
+    }
+
+    for player in 0..players.0 {
+        spawn_ship(&mut commands, &scenario, Player(player));
+    }
+
+    for (mut transform, mut projection) in camera.iter_mut() {
+        transform.translation = scenario.camera.center.extend(transform.translation.z);
+        if let Projection::Orthographic(projection) = projection.as_mut() {
+            projection.scale = scenario.camera.scale;
+        }
+    }
+}
+
+fn respawn_ships(
+    mut commands: Commands,
+    ships: Query<(Entity, &Player), With<Ship>>,
+    inputs: Res<Inputs>,
+    scenario: Res<Scenario>,
+) {
+    if !scenario.rules.respawn {
+        return;
+    }
+
+    for (index, input) in inputs.0.iter().enumerate() {
+        if !input.respawn {
+            continue;
+        }
+
+        let player = Player(index);
+        for (entity, _) in ships.iter().filter(|(_, owner)| **owner == player) {
+            commands.entity(entity).despawn();
+        }
+
+        spawn_ship(&mut commands, &scenario, player);
+    }
+}
+
+fn zoom(
+    mut camera: Single<&mut Projection, With<Camera2d>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<KeyBinds>,
+) {
+    let projection = match **camera.deref_mut() {
+        Projection::Orthographic(ref mut projection) => projection,
+        _ => unimplemented!(),
+    };
+
+    if keys.any_pressed(keybinds.zoom().map(KeyPair::left)) {
+        projection.scale *= 1.025
+    } else if keys.any_pressed(keybinds.zoom().map(KeyPair::right)) {
+        projection.scale *= 0.975
+    }
+}
+
+/// Sets up a match: the camera, the stars and every player's ship.
+#[derive(Clone, Debug, Default)]
+pub struct Plugin {
+    pub keybinds: KeyBinds,
+    pub scenario: Scenario,
+}
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GravityField>()
+            .init_resource::<Inputs>()
+            .init_resource::<LocalPlayer>()
+            .init_resource::<Players>()
+            .init_resource::<SimulationRng>()
+            .insert_resource(self.keybinds.clone())
+            .insert_resource(self.scenario.clone())
+            .add_systems(
+                PreUpdate,
+                (start_match, star::sync_gravity)
+                    .chain()
+                    .run_if(resource_added::<Scenario>)
+                    .after(scenario::finish_loading),
+            )
+            // after everything's moved, so fresh ships start the next tick right at the spawn
+            .add_systems(FixedUpdate, respawn_ships.after(kinematics::KinematicsSet));
+
+        if crate::has_renderer(app) {
+            app.add_systems(Startup, spawn_camera)
+                .add_systems(Update, zoom);
+        }
+    }
+}
+
+/// Everything the simulation itself is made of, shared by [`SpacewarPlugins`] and
+/// [`HeadlessPlugins`](crate::HeadlessPlugins).
+pub(crate) fn simulation_plugins(
+    builder: PluginGroupBuilder,
+    keybinds: KeyBinds,
+    scenario: Scenario,
+) -> PluginGroupBuilder {
+    builder
+        .add(settings::Plugin::default())
+        .add(transform2d::Plugin)
+        .add(kinematics::Plugin)
+        .add(conservation::Plugin)
+        .add(warp::Plugin)
+        .add(pause::Plugin)
+        .add(input::Plugin)
+        .add(rewind::Plugin)
+        .add(star::Plugin)
+        .add(ship::Plugin)
+        .add(missile::Plugin)
+        .add(mine::Plugin)
+        .add(beam::Plugin)
+        .add(fragment::Plugin)
+        .add(turret::Plugin)
+        .add(Plugin { keybinds, scenario })
+        .add(scenario::Plugin::default())
+        .add(replay::Plugin::default())
+        .add(save::Plugin::default())
+}
+
+/// The whole game, to be added on top of Bevy's `DefaultPlugins`.
+///
+/// Individual plugins can be left out the usual way:
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use spacewar::{SpacewarPlugins, turret};
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(SpacewarPlugins::default().build().disable::<turret::Plugin>())
+///     .run();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SpacewarPlugins {
+    pub keybinds: KeyBinds,
+    pub scenario: Scenario,
+}
+
+impl PluginGroup for SpacewarPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        simulation_plugins(
+            PluginGroupBuilder::start::<Self>(),
+            self.keybinds,
+            self.scenario,
+        )
+        .add(debug_info::Plugin::default())
+    }
+}
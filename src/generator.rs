@@ -0,0 +1,260 @@
+use crate::{
+    gravity::GRAVITATIONAL_CONSTANT,
+    scenario::{Body, CameraStart, Placement, Scenario, Spawn},
+};
+use bevy::math::Vec2;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::ops::RangeInclusive;
+
+/// How far either side of the spawn orbit nothing ever comes, as a fraction of its radius.
+pub const SPAWN_BAND: f32 = 0.3;
+
+/// The innermost planet's periapsis, less its moons, as a multiple of the spawn orbit's radius,
+/// so that planets pull on ships there far less than the stars do.
+const FIRST_PLANET: f32 = 2.0;
+
+/// Moons are kept within this fraction of their planet's Hill radius at periapsis, where
+/// they'd actually stay if anything pulled on them but their planet.
+const MOON_REACH: f32 = 0.4;
+
+/// Space left between one orbit and the next, on top of the room each body takes up.
+const ORBIT_GAP: f32 = 300.0;
+const MOON_GAP: f32 = 8.0;
+
+/// Makes up a star system from a seed: a star or a pair of them, planets going around with
+/// moons of their own, and ships starting out on a circular orbit clear of all of it.
+///
+/// Every orbit keeps to a ring of its own around whatever it's going around, wide enough for
+/// anything going around it in turn, so nothing ever runs into anything else. Ships feel the
+/// planets and moons pulling on them, unlike the bodies themselves, which follow their orbits
+/// on rails.
+///
+/// ```
+/// # use spacewar::generator::Generator;
+/// let system = Generator::default().generate(7);
+/// assert_eq!(system, Generator::default().generate(7));
+/// assert_eq!(system.seed, 7);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Generator {
+    pub planets: RangeInclusive<usize>,
+    pub moons: RangeInclusive<usize>,
+    /// how likely the primary is to have a companion star
+    pub binary_chance: f64,
+    /// the same as the [`GravityField`](crate::GravityField) the system is played in, for
+    /// the ships' starting speed
+    pub gravitational_constant: f32,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self {
+            planets: 2..=4,
+            moons: 0..=2,
+            binary_chance: 0.35,
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+        }
+    }
+}
+
+/// Where something goes around whatever it's orbiting, by its elements.
+struct Ring {
+    semi_major_axis: f32,
+    eccentricity: f32,
+}
+
+impl Ring {
+    fn random(rng: &mut impl Rng, periapsis: f32, max_eccentricity: f32) -> Self {
+        let eccentricity = rng.gen_range(0.0..max_eccentricity);
+        Self {
+            semi_major_axis: periapsis / (1.0 - eccentricity),
+            eccentricity,
+        }
+    }
+
+    fn apoapsis(&self) -> f32 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    fn placement(&self, rng: &mut impl Rng, around: &str) -> Placement {
+        Placement::Elements {
+            around: around.into(),
+            semi_major_axis: self.semi_major_axis as f64,
+            eccentricity: self.eccentricity as f64,
+            argument_of_periapsis: rng.gen_range(0.0..360.0),
+            true_anomaly: rng.gen_range(0.0..360.0),
+            prograde: true,
+        }
+    }
+}
+
+impl Generator {
+    pub fn generate(&self, seed: u64) -> Scenario {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        let primary = Body {
+            name: "primary".into(),
+            mass: rng.gen_range(1.2e16..2.4e16),
+            radius: rng.gen_range(8.0..16.0),
+            placement: Placement::Fixed(Vec2::ZERO),
+        };
+        let mut inner_mass = primary.mass;
+        // farthest out the stars reach
+        let mut inner_edge = primary.radius;
+        let mut bodies = vec![primary.clone()];
+
+        if rng.gen_bool(self.binary_chance) {
+            let periapsis = rng.gen_range(1200.0..2000.0);
+            let ring = Ring::random(&mut rng, periapsis, 0.2);
+            let companion = Body {
+                name: "companion".into(),
+                mass: primary.mass * rng.gen_range(0.2..0.5),
+                radius: primary.radius * rng.gen_range(0.4..0.7),
+                placement: ring.placement(&mut rng, &primary.name),
+            };
+            inner_mass += companion.mass;
+            inner_edge = ring.apoapsis() + companion.radius;
+            bodies.push(companion);
+        }
+
+        let spawn_radius = (inner_edge * 2.0).max(rng.gen_range(1000.0..1400.0));
+        let spawn_speed = (self.gravitational_constant * inner_mass / spawn_radius).sqrt();
+        let spawn_direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+        let spawn = Spawn {
+            position: spawn_direction * spawn_radius,
+            velocity: spawn_direction.perp() * spawn_speed,
+        };
+        // the second player starts on the opposite side, heading the opposite way
+        let spawns = vec![
+            spawn,
+            Spawn {
+                position: -spawn.position,
+                velocity: -spawn.velocity,
+            },
+        ];
+
+        let mut outer_edge = spawn_radius * FIRST_PLANET;
+        for planet in 1..=rng.gen_range(self.planets.clone()) {
+            let name = format!("planet {planet}");
+            let mass = rng.gen_range(1e14..8e14);
+            let radius = rng.gen_range(3.0..7.0);
+
+            let mut moons = Vec::new();
+            let mut reach = radius;
+            for moon in 1..=rng.gen_range(self.moons.clone()) {
+                let periapsis = reach * 2.0 + MOON_GAP + rng.gen_range(0.0..20.0);
+                let ring = Ring::random(&mut rng, periapsis, 0.05);
+                let body = Body {
+                    name: format!("{name} moon {moon}"),
+                    mass: rng.gen_range(1e12..1e13),
+                    radius: rng.gen_range(1.0..2.5),
+                    placement: ring.placement(&mut rng, &name),
+                };
+                reach = ring.apoapsis() + body.radius;
+                moons.push(body);
+            }
+
+            // close enough in, the star would pull the moons away from the planet
+            let hill_fraction = (mass / (3.0 * primary.mass)).cbrt();
+            let periapsis = (outer_edge + reach + rng.gen_range(0.0..800.0))
+                .max(reach / (MOON_REACH * hill_fraction));
+            let ring = Ring::random(&mut rng, periapsis, 0.15);
+
+            bodies.push(Body {
+                name,
+                mass,
+                radius,
+                placement: ring.placement(&mut rng, &primary.name),
+            });
+            bodies.extend(moons);
+            outer_edge = ring.apoapsis() + reach + ORBIT_GAP;
+        }
+
+        Scenario {
+            bodies,
+            spawns,
+            camera: CameraStart {
+                center: Vec2::ZERO,
+                scale: spawn_radius / 1250.0,
+            },
+            seed,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kepler::Orbit;
+
+    /// Where every body is at `time`, the same way stars following their orbits are placed.
+    fn positions(scenario: &Scenario, orbits: &[Option<(usize, Orbit)>], time: f64) -> Vec<Vec2> {
+        let mut positions: Vec<Vec2> = Vec::with_capacity(orbits.len());
+        for (body, orbit) in scenario.bodies.iter().zip(orbits) {
+            positions.push(match (orbit, &body.placement) {
+                (Some((parent, orbit)), _) => positions[*parent] + orbit.after(time).state().0,
+                (None, Placement::Fixed(position)) => *position,
+                (None, _) => unreachable!(),
+            });
+        }
+        positions
+    }
+
+    #[test]
+    fn same_seed_same_system() {
+        let generator = Generator::default();
+        assert_eq!(generator.generate(42), generator.generate(42));
+        assert_ne!(generator.generate(42), generator.generate(43));
+    }
+
+    #[test]
+    fn nothing_collides_or_crosses_the_spawn_band() {
+        const HORIZON: f64 = 600.0;
+        const STEP: f64 = 0.1;
+
+        let generator = Generator::default();
+        let mut binaries = 0;
+        for seed in 0..16 {
+            let scenario = generator.generate(seed);
+            let orbits: Vec<_> = (0..scenario.bodies.len())
+                .map(|index| {
+                    scenario
+                        .orbit(index, generator.gravitational_constant)
+                        .unwrap()
+                })
+                .collect();
+            binaries += scenario.bodies.iter().any(|body| body.name == "companion") as usize;
+            assert!(scenario.bodies.len() > *generator.planets.start());
+
+            let spawn_radius = scenario.spawns[0].position.length();
+            let band = spawn_radius * (1.0 - SPAWN_BAND)..=spawn_radius * (1.0 + SPAWN_BAND);
+
+            for step in 0..=(HORIZON / STEP) as u32 {
+                let positions = positions(&scenario, &orbits, step as f64 * STEP);
+                for (i, (a, position)) in scenario.bodies.iter().zip(&positions).enumerate() {
+                    let distance = position.length();
+                    assert!(
+                        distance + a.radius < *band.start() || distance - a.radius > *band.end(),
+                        "seed {seed}: {} crosses the spawn band at {distance}",
+                        a.name
+                    );
+
+                    for (b, other) in scenario.bodies.iter().zip(&positions).skip(i + 1) {
+                        assert!(
+                            position.distance(*other) > a.radius + b.radius,
+                            "seed {seed}: {} runs into {} at {}",
+                            a.name,
+                            b.name,
+                            step as f64 * STEP
+                        );
+                    }
+                }
+            }
+        }
+
+        // both kinds come up
+        assert!(binaries > 0 && binaries < 16, "{binaries} binaries");
+    }
+}
@@ -1,38 +1,144 @@
-use bevy::math::Vec2;
+use bevy::{
+    ecs::{
+        entity::{Entity, EntityHashMap},
+        reflect::ReflectResource,
+    },
+    math::Vec2,
+    reflect::Reflect,
+};
+use serde::{Deserialize, Serialize};
 pub const GRAVITATIONAL_CONSTANT: f32 = 6.6743e-11;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
 pub struct Mass {
     pub translation: Vec2,
     pub mass: f32, // in kg
 }
 
 impl Mass {
-    fn acceleration_to(&self, from_point: Vec2) -> Vec2 {
+    fn acceleration_to(&self, from_point: Vec2, gravitational_constant: f32) -> Vec2 {
         let displacement = from_point - self.translation;
-        -GRAVITATIONAL_CONSTANT * self.mass / displacement.length_squared()
+        -gravitational_constant * self.mass / displacement.length_squared()
             * displacement.normalize_or_zero()
     }
+
+    fn potential_at(&self, point: Vec2, gravitational_constant: f32) -> f32 {
+        -gravitational_constant * self.mass / point.distance(self.translation)
+    }
 }
 
-#[derive(bevy::prelude::Resource, Debug, Default)]
+/// Every mass pulling on things, each optionally tied to the entity it came from so it can be
+/// kept up to date.
+#[derive(bevy::prelude::Resource, Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
 pub struct GravityField {
-    pub masses: Vec<Mass>,
+    masses: Vec<(Option<Entity>, Mass)>,
+    gravitational_constant: f32,
+}
+
+impl Default for GravityField {
+    fn default() -> Self {
+        Self {
+            masses: Vec::new(),
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+        }
+    }
 }
 
 impl GravityField {
+    /// Summed in the order the masses were added, so that it comes out the same every time.
     pub fn acceleration_at(&self, point: Vec2) -> Vec2 {
+        self.masses()
+            .map(|mass| mass.acceleration_to(point, self.gravitational_constant))
+            .sum()
+    }
+
+    /// Gravitational potential energy per unit mass.
+    pub fn potential_at(&self, point: Vec2) -> f32 {
+        self.masses()
+            .map(|mass| mass.potential_at(point, self.gravitational_constant))
+            .sum()
+    }
+
+    /// The mass pulling hardest on `point`, which is what it's orbiting as far as patched conics
+    /// are concerned.
+    pub fn dominant_mass_at(&self, point: Vec2) -> Option<&Mass> {
+        self.masses().max_by(|a, b| {
+            let pull = |mass: &Mass| mass.mass / point.distance_squared(mass.translation);
+            pull(a).total_cmp(&pull(b))
+        })
+    }
+
+    pub fn gravitational_constant(&self) -> f32 {
+        self.gravitational_constant
+    }
+
+    pub fn set_gravitational_constant(&mut self, gravitational_constant: f32) {
+        self.gravitational_constant = gravitational_constant;
+    }
+
+    /// Center of mass of the whole field, or the origin if it's empty.
+    pub fn barycenter(&self) -> Vec2 {
+        let total: f32 = self.masses().map(|mass| mass.mass).sum();
+        if total == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        self.masses()
+            .map(|mass| mass.translation * mass.mass)
+            .sum::<Vec2>()
+            / total
+    }
+
+    pub fn masses(&self) -> impl ExactSizeIterator<Item = &Mass> {
+        self.masses.iter().map(|(_, mass)| mass)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&Mass> {
         self.masses
             .iter()
-            .map(|mass| mass.acceleration_to(point))
-            .sum()
+            .find(|(source, _)| *source == Some(entity))
+            .map(|(_, mass)| mass)
+    }
+
+    /// Adds the mass belonging to `entity`, or replaces it if there already is one.
+    pub fn insert(&mut self, entity: Entity, mass: Mass) {
+        match self
+            .masses
+            .iter_mut()
+            .find(|(source, _)| *source == Some(entity))
+        {
+            Some((_, existing)) => *existing = mass,
+            None => self.masses.push((Some(entity), mass)),
+        }
+    }
+
+    /// Points masses at the entities they were mapped to, after those were spawned again.
+    pub fn remap(&mut self, map: &EntityHashMap<Entity>) {
+        for (source, _) in &mut self.masses {
+            if let Some(entity) = source
+                && let Some(mapped) = map.get(entity)
+            {
+                *entity = *mapped;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<Mass> {
+        let index = self
+            .masses
+            .iter()
+            .position(|(source, _)| *source == Some(entity))?;
+
+        // keep the order stable, since it changes how the accelerations get summed
+        Some(self.masses.remove(index).1)
     }
 
     pub fn trajectory_starting_at(
         &self,
         start: crate::TrajectoryNode,
         delta_secs: f32,
-    ) -> crate::Trajectory {
+    ) -> crate::Trajectory<'_> {
         crate::Trajectory {
             state: start,
             gravity: self,
@@ -42,10 +148,12 @@ impl GravityField {
     }
 }
 
+/// A field of masses that don't belong to any entity.
 impl FromIterator<Mass> for GravityField {
     fn from_iter<T: IntoIterator<Item = Mass>>(iter: T) -> Self {
         Self {
-            masses: iter.into_iter().collect(),
+            masses: iter.into_iter().map(|mass| (None, mass)).collect(),
+            ..Default::default()
         }
     }
 }
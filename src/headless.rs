@@ -0,0 +1,83 @@
+use crate::{
+    KeyBinds, Scenario, Ship, Transform2d, Velocity, game, kinematics::SimulationTick, ship::Health,
+};
+use bevy::{app::PluginGroupBuilder, input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+
+/// The whole game without a window or renderer, for running matches as fast as possible.
+///
+/// Every [`App::update`] advances the simulation by exactly one fixed tick, regardless of how
+/// long it took in real time:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use spacewar::{HeadlessPlugins, headless};
+/// let mut app = App::new();
+/// app.add_plugins(HeadlessPlugins::default());
+/// headless::run_ticks(&mut app, 64);
+/// assert_eq!(app.world().resource::<Time<Fixed>>().elapsed_secs(), 1.0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HeadlessPlugins {
+    pub keybinds: KeyBinds,
+    pub scenario: Scenario,
+}
+
+impl PluginGroup for HeadlessPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        let builder = PluginGroupBuilder::start::<Self>()
+            .add_group(MinimalPlugins)
+            // for scenario files
+            .add(AssetPlugin::default())
+            // controls still read `ButtonInput`, there's just nothing pressing the buttons
+            .add(InputPlugin)
+            .add(Plugin);
+
+        game::simulation_plugins(builder, self.keybinds, self.scenario)
+    }
+}
+
+/// Steps virtual time by one fixed timestep per update instead of following the wall clock.
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let timestep = app
+            .world()
+            .get_resource::<Time<Fixed>>()
+            .map(Time::timestep)
+            .unwrap_or_else(|| Time::<Fixed>::default().timestep());
+
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+    }
+}
+
+/// Runs the app for `ticks` fixed ticks, as fast as it'll go, or until something asks it to exit.
+pub fn run_ticks(app: &mut App, ticks: u32) {
+    if app.plugins_state() == bevy::app::PluginsState::Ready {
+        app.finish();
+        app.cleanup();
+    }
+
+    // the very first update only starts the clock, and the timestep can change along the way, so
+    // count ticks rather than updates or time
+    let tick = |app: &App| app.world().resource::<SimulationTick>().0;
+    let target = tick(app) + u64::from(ticks);
+
+    while tick(app) < target && app.should_exit().is_none() {
+        app.update();
+    }
+}
+
+/// Where every ship ended up, one line each, for reporting at the end of a run.
+pub fn ship_summary(world: &mut World) -> Vec<String> {
+    let mut ships = world.query_filtered::<(&Transform2d, &Velocity, &Health), With<Ship>>();
+    ships
+        .iter(world)
+        .map(|(transform, velocity, health)| {
+            format!(
+                "ship at {} moving at {} with {} health",
+                transform.translation, velocity.0, health.0
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+use crate::{KeyBinds, KeyPair};
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Everything a player can tell their ship to do in one fixed tick, whether it came from a
+/// keyboard, a replay or over the network.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShipInput {
+    pub thrust: bool,
+    /// `1` to turn anticlockwise, `-1` clockwise
+    pub turn: i8,
+    pub toggle_prograde: bool,
+    pub toggle_sas: bool,
+    pub fire_missile: bool,
+    pub deploy_mine: bool,
+    pub fire_beam: bool,
+    /// replace the ship with a fresh one at the spawn point, or bring it back if destroyed
+    pub respawn: bool,
+}
+
+impl ShipInput {
+    /// Forgets the one-off actions, keeping only what's held down.
+    pub fn release_presses(&mut self) {
+        *self = Self {
+            thrust: self.thrust,
+            turn: self.turn,
+            ..Default::default()
+        };
+    }
+}
+
+/// Which player a ship belongs to, indexing into [`Inputs`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Component, Serialize, Deserialize)]
+pub struct Player(pub usize);
+
+/// Input for every player, for the coming fixed tick.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Resource)]
+pub struct Inputs(pub Vec<ShipInput>);
+
+impl Inputs {
+    pub fn get(&self, player: Player) -> ShipInput {
+        self.0.get(player.0).copied().unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, player: Player) -> &mut ShipInput {
+        if self.0.len() <= player.0 {
+            self.0.resize(player.0 + 1, ShipInput::default());
+        }
+        &mut self.0[player.0]
+    }
+}
+
+/// The player controlled from this keyboard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub struct LocalPlayer(pub Player);
+
+/// How many players the match has, each getting a ship.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource)]
+pub struct Players(pub usize);
+
+impl Default for Players {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// What the keyboard has asked for since the last tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource)]
+pub struct LocalInput {
+    pub input: ShipInput,
+    /// whether it goes straight into [`Inputs`] every tick, rather than being left for something
+    /// like networking to [`take`](Self::take) and pass on in its own time
+    pub direct: bool,
+}
+
+impl Default for LocalInput {
+    fn default() -> Self {
+        Self {
+            input: ShipInput::default(),
+            direct: true,
+        }
+    }
+}
+
+impl LocalInput {
+    /// The input for one tick, keeping only what's held down for the next.
+    pub fn take(&mut self) -> ShipInput {
+        let input = self.input;
+        self.input.release_presses();
+        input
+    }
+}
+
+/// Anything filling in [`Inputs`] before a tick, so anything acting on them should run after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct InputSet;
+
+/// Held keys are read every frame, while presses are kept until a tick has had a chance to act on
+/// them.
+fn read_keyboard(
+    mut local: ResMut<LocalInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<KeyBinds>,
+    time: Res<Time<Virtual>>,
+) {
+    let input = &mut local.input;
+
+    input.thrust = keys.any_pressed(keybinds.accelerate());
+    input.turn = if keys.any_pressed(keybinds.rotation_speed().map(KeyPair::left)) {
+        1
+    } else if keys.any_pressed(keybinds.rotation_speed().map(KeyPair::right)) {
+        -1
+    } else {
+        0
+    };
+
+    // nothing gets fired off while paused, even when stepping
+    if time.is_paused() {
+        return;
+    }
+
+    input.toggle_prograde |= keys.just_pressed(KeyCode::KeyP);
+    input.toggle_sas |= keys.just_pressed(KeyCode::KeyT);
+    input.fire_missile |= keys.any_just_pressed(keybinds.fire_missile());
+    input.deploy_mine |= keys.any_just_pressed(keybinds.deploy_mine());
+    input.fire_beam |= keys.any_just_pressed(keybinds.fire_beam());
+    input.respawn |= keys.any_just_pressed(keybinds.reset());
+}
+
+/// Hands the keyboard's input to the [`LocalPlayer`], unless it's being passed on some other way.
+pub(crate) fn apply_local_input(
+    mut inputs: ResMut<Inputs>,
+    mut local: ResMut<LocalInput>,
+    player: Res<LocalPlayer>,
+) {
+    if local.direct {
+        *inputs.get_mut(player.0) = local.take();
+    }
+}
+
+fn release_presses(mut inputs: ResMut<Inputs>) {
+    for input in inputs.0.iter_mut() {
+        input.release_presses();
+    }
+}
+
+/// Turns keyboard input into [`Inputs`] for the [`LocalPlayer`].
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inputs>()
+            .init_resource::<LocalPlayer>()
+            .init_resource::<LocalInput>()
+            .add_systems(PreUpdate, read_keyboard.after(InputSystem))
+            .add_systems(FixedPreUpdate, apply_local_input.in_set(InputSet))
+            .add_systems(FixedPostUpdate, release_presses);
+    }
+}
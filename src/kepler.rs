@@ -0,0 +1,245 @@
+use crate::float::{asinh, atan, atan2, atanh, cos, cosh, sin, sinh, sqrt, tan, tanh};
+use bevy::math::{DVec2, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// A two-body orbit around a single mass, for moving things along analytically instead of
+/// integrating them step by step.
+///
+/// Everything is relative to the mass being orbited, and worked out in `f64` so that orbits
+/// stay put even when propagated a long way at once.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Orbit {
+    /// gravitational constant times the mass being orbited
+    pub mu: f64,
+    /// negative for hyperbolic orbits
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    /// angle of periapsis from the x axis, in radians
+    pub argument_of_periapsis: f64,
+    /// at the state this orbit was made from, in radians
+    pub true_anomaly: f64,
+    /// whether the orbit goes anticlockwise
+    pub prograde: bool,
+}
+
+/// Closer to parabolic than this and the elliptic and hyperbolic formulas both fall apart.
+const PARABOLIC_TOLERANCE: f64 = 1e-6;
+
+impl Orbit {
+    /// The orbit passing through `position` at `velocity`, or nothing if it's parabolic or
+    /// falling straight in, neither of which can be propagated here.
+    pub fn from_state(position: Vec2, velocity: Vec2, mu: f32) -> Option<Self> {
+        let (r, v, mu) = (position.as_dvec2(), velocity.as_dvec2(), mu as f64);
+
+        let angular_momentum = r.perp_dot(v);
+        let energy = v.length_squared() / 2.0 - mu / r.length();
+        if angular_momentum.abs() < f64::EPSILON * r.length() * v.length()
+            || energy.abs() < PARABOLIC_TOLERANCE * mu / r.length()
+        {
+            return None;
+        }
+
+        let eccentricity_vector = ((v.length_squared() - mu / r.length()) * r - r.dot(v) * v) / mu;
+        let eccentricity = eccentricity_vector.length();
+        if (eccentricity - 1.0).abs() < PARABOLIC_TOLERANCE {
+            return None;
+        }
+
+        // circular orbits have no periapsis, so measure from the x axis instead
+        let argument_of_periapsis = if eccentricity < PARABOLIC_TOLERANCE {
+            0.0
+        } else {
+            eccentricity_vector.to_angle()
+        };
+
+        let prograde = angular_momentum > 0.0;
+        let direction = if prograde { 1.0 } else { -1.0 };
+
+        Some(Self {
+            mu,
+            semi_major_axis: -mu / (2.0 * energy),
+            eccentricity,
+            argument_of_periapsis,
+            true_anomaly: wrap_angle(direction * (r.to_angle() - argument_of_periapsis)),
+            prograde,
+        })
+    }
+
+    pub fn is_hyperbolic(&self) -> bool {
+        self.eccentricity > 1.0
+    }
+
+    /// semi-latus rectum
+    fn parameter(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity * self.eccentricity)
+    }
+
+    fn mean_motion(&self) -> f64 {
+        let a = self.semi_major_axis.abs();
+        sqrt(self.mu / (a * a * a))
+    }
+
+    pub fn period(&self) -> Option<f64> {
+        (!self.is_hyperbolic()).then(|| std::f64::consts::TAU / self.mean_motion())
+    }
+
+    pub fn periapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    /// Farthest distance from the mass, which hyperbolic orbits don't have.
+    pub fn apoapsis(&self) -> Option<f64> {
+        (!self.is_hyperbolic()).then_some(self.semi_major_axis * (1.0 + self.eccentricity))
+    }
+
+    /// The same orbit, `delta_secs` later.
+    pub fn after(&self, delta_secs: f64) -> Self {
+        let e = self.eccentricity;
+        let mean_anomaly = self.mean_anomaly() + self.mean_motion() * delta_secs;
+
+        let true_anomaly = if self.is_hyperbolic() {
+            let anomaly = solve_hyperbolic(mean_anomaly, e);
+            2.0 * atan(sqrt((e + 1.0) / (e - 1.0)) * tanh(anomaly / 2.0))
+        } else {
+            let anomaly = solve_elliptic(wrap_angle(mean_anomaly), e);
+            2.0 * atan2(
+                sqrt(1.0 + e) * sin(anomaly / 2.0),
+                sqrt(1.0 - e) * cos(anomaly / 2.0),
+            )
+        };
+
+        Self {
+            true_anomaly,
+            ..*self
+        }
+    }
+
+    fn mean_anomaly(&self) -> f64 {
+        let (e, nu) = (self.eccentricity, self.true_anomaly);
+
+        if self.is_hyperbolic() {
+            let anomaly = 2.0 * atanh(sqrt((e - 1.0) / (e + 1.0)) * tan(nu / 2.0));
+            e * sinh(anomaly) - anomaly
+        } else {
+            let anomaly = 2.0 * atan2(sqrt(1.0 - e) * sin(nu / 2.0), sqrt(1.0 + e) * cos(nu / 2.0));
+            anomaly - e * sin(anomaly)
+        }
+    }
+
+    /// Position and velocity relative to the mass being orbited.
+    pub fn state(&self) -> (Vec2, Vec2) {
+        let (e, nu) = (self.eccentricity, self.true_anomaly);
+        let direction = if self.prograde { 1.0 } else { -1.0 };
+
+        let angle = self.argument_of_periapsis + direction * nu;
+        let radial = DVec2::from_angle(angle);
+        let tangential = radial.perp() * direction;
+
+        let distance = self.parameter() / (1.0 + e * cos(nu));
+        let speed_scale = sqrt(self.mu / self.parameter());
+
+        let position = distance * radial;
+        let velocity = speed_scale * (e * sin(nu) * radial + (1.0 + e * cos(nu)) * tangential);
+
+        (position.as_vec2(), velocity.as_vec2())
+    }
+}
+
+fn wrap_angle(angle: f64) -> f64 {
+    use std::f64::consts::{PI, TAU};
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// Eccentric anomaly for a mean anomaly, by Newton's method.
+fn solve_elliptic(mean_anomaly: f64, e: f64) -> f64 {
+    let mut anomaly = if e > 0.8 {
+        std::f64::consts::PI.copysign(mean_anomaly)
+    } else {
+        mean_anomaly
+    };
+
+    for _ in 0..50 {
+        let step = (anomaly - e * sin(anomaly) - mean_anomaly) / (1.0 - e * cos(anomaly));
+        anomaly -= step;
+        if step.abs() < 1e-12 {
+            break;
+        }
+    }
+
+    anomaly
+}
+
+/// Hyperbolic anomaly for a mean anomaly, by Newton's method.
+fn solve_hyperbolic(mean_anomaly: f64, e: f64) -> f64 {
+    let mut anomaly = asinh(2.0 * mean_anomaly / e);
+
+    for _ in 0..50 {
+        let step = (e * sinh(anomaly) - anomaly - mean_anomaly) / (e * cosh(anomaly) - 1.0);
+        anomaly -= step;
+        if step.abs() < 1e-12 {
+            break;
+        }
+    }
+
+    anomaly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MU: f32 = 1.0e6;
+
+    fn assert_close(a: Vec2, b: Vec2, tolerance: f32) {
+        assert!(a.distance(b) < tolerance, "{a} is not close to {b}");
+    }
+
+    #[test]
+    fn round_trips_state() {
+        for (position, velocity) in [
+            (Vec2::new(1000.0, 0.0), Vec2::new(0.0, 20.0)),
+            (Vec2::new(-300.0, 800.0), Vec2::new(-40.0, 5.0)),
+            (Vec2::new(500.0, 500.0), Vec2::new(30.0, -90.0)),
+        ] {
+            let orbit = Orbit::from_state(position, velocity, MU).unwrap();
+            let (p, v) = orbit.state();
+            assert_close(p, position, 1e-2);
+            assert_close(v, velocity, 1e-3);
+        }
+    }
+
+    #[test]
+    fn circular_orbit_quarter_turn() {
+        let radius = 1000.0;
+        let speed = (MU / radius).sqrt();
+        let orbit = Orbit::from_state(Vec2::new(radius, 0.0), Vec2::new(0.0, -speed), MU).unwrap();
+
+        let (position, velocity) = orbit.after(orbit.period().unwrap() / 4.0).state();
+        assert_close(position, Vec2::new(0.0, -radius), 1e-2);
+        assert_close(velocity, Vec2::new(-speed, 0.0), 1e-3);
+    }
+
+    #[test]
+    fn full_period_comes_back() {
+        let (position, velocity) = (Vec2::new(-300.0, 800.0), Vec2::new(-40.0, 5.0));
+        let orbit = Orbit::from_state(position, velocity, MU).unwrap();
+        assert!(!orbit.is_hyperbolic());
+
+        let (p, v) = orbit.after(orbit.period().unwrap() * 3.0).state();
+        assert_close(p, position, 1e-2);
+        assert_close(v, velocity, 1e-3);
+    }
+
+    #[test]
+    fn hyperbolic_orbit_conserves_energy() {
+        let orbit = Orbit::from_state(Vec2::new(1000.0, 0.0), Vec2::new(-20.0, 60.0), MU).unwrap();
+        assert!(orbit.is_hyperbolic());
+
+        let energy = |(p, v): (Vec2, Vec2)| v.length_squared() / 2.0 - MU / p.length();
+        let before = energy(orbit.state());
+        for delta in [10.0, 100.0, 1000.0] {
+            let after = energy(orbit.after(delta).state());
+            assert!((after - before).abs() < before.abs() * 1e-4);
+        }
+    }
+}
@@ -35,8 +35,17 @@ keybinds_struct! {
     rotation_speed: KeyPair [KeyPair::ARROWS_LR, KeyPair::KEY_AD]
     accelerate: KeyCode [KeyCode::ArrowUp, KeyCode::KeyW]
     trajectory_length: KeyPair [KeyPair::BRACKETS]
+    fire_missile: KeyCode [KeyCode::Space]
+    deploy_mine: KeyCode [KeyCode::KeyM]
+    fire_beam: KeyCode [KeyCode::KeyF]
+    time_warp: KeyPair [KeyPair::MINUS_EQUAL]
+    pause: KeyCode [KeyCode::Pause, KeyCode::Backquote]
+    step: KeyCode [KeyCode::KeyN]
+    rewind: KeyPair [KeyPair::KEY_ZX]
     reset: KeyCode [KeyCode::KeyR]
     toggle_debug_menu: KeyCode [KeyCode::F3]
+    quicksave: KeyCode [KeyCode::F5]
+    quickload: KeyCode [KeyCode::F9]
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -61,6 +70,8 @@ impl KeyPair {
     pub const ARROWS_LR: Self = Self(KeyCode::ArrowLeft, KeyCode::ArrowRight);
     pub const COMMA_PERIOD: Self = Self(KeyCode::Comma, KeyCode::Period);
     pub const KEY_AD: Self = Self(KeyCode::KeyA, KeyCode::KeyD);
+    pub const KEY_ZX: Self = Self(KeyCode::KeyZ, KeyCode::KeyX);
+    pub const MINUS_EQUAL: Self = Self(KeyCode::Minus, KeyCode::Equal);
 
     keypair_getters! {
         left right
@@ -0,0 +1,105 @@
+use crate::{GravityField, SimulationSettings, TrajectoryNode, Transform2d, kepler::Orbit};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Linear velocity of anything that moves through the [`GravityField`], in units per second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component, Serialize, Deserialize)]
+#[require(Transform2d)]
+pub struct Velocity(pub Vec2);
+
+/// Keeps an entity pointing the way it's moving.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct AlignToVelocity;
+
+/// Fixed ticks simulated so far, counted at the start of each one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Resource)]
+pub struct SimulationTick(pub u64);
+
+/// Seconds simulated so far, which unlike [`Time<Fixed>`] goes back along with everything else
+/// when rewound.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
+pub struct SimulationTime(pub f64);
+
+fn count_tick(
+    mut tick: ResMut<SimulationTick>,
+    mut simulated: ResMut<SimulationTime>,
+    time: Res<Time>,
+) {
+    tick.0 += 1;
+    simulated.0 += time.delta_secs_f64();
+}
+
+/// Everything moving under gravity gets moved here, so anything reacting to where bodies ended
+/// up should run after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct KinematicsSet;
+
+/// Whether bodies are moved along Kepler orbits around whatever they're closest to orbiting,
+/// rather than integrated. Much cheaper over long ticks, but nothing but gravity can act on them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct OnRails(pub bool);
+
+fn integrate(
+    mut bodies: Query<(&mut Transform2d, &mut Velocity)>,
+    gravity: Res<GravityField>,
+    settings: Res<SimulationSettings>,
+    on_rails: Res<OnRails>,
+    time: Res<Time>,
+) {
+    // ticks can be longer than usual under time warp, but each step shouldn't be
+    let max_step = settings.timestep().as_secs_f32() / settings.substeps.max(1) as f32;
+    let substeps = (time.delta_secs() / max_step).round().max(1.0) as u32;
+
+    for (mut transform, mut velocity) in bodies.iter_mut() {
+        if on_rails.0
+            && let Some(center) = gravity.dominant_mass_at(transform.translation)
+            && let Some(orbit) = Orbit::from_state(
+                transform.translation - center.translation,
+                velocity.0,
+                gravity.gravitational_constant() * center.mass,
+            )
+        {
+            let (position, next_velocity) = orbit.after(time.delta_secs_f64()).state();
+            transform.translation = center.translation + position;
+            velocity.0 = next_velocity;
+            continue;
+        }
+
+        let mut trajectory = gravity.trajectory_starting_at(
+            TrajectoryNode::from_translation_velocity(transform.translation, velocity.0),
+            time.delta_secs() / substeps as f32,
+        );
+        let next_node = (0..substeps)
+            .map(|_| trajectory.next_guaranteed())
+            .last()
+            .unwrap_or(trajectory.state);
+
+        velocity.0 = next_node.velocity();
+        transform.translation = next_node.translation();
+    }
+}
+
+fn align_to_velocity(mut bodies: Query<(&mut Transform2d, &Velocity), With<AlignToVelocity>>) {
+    for (mut transform, velocity) in bodies.iter_mut() {
+        if velocity.0 != Vec2::ZERO {
+            transform.rotation = Rot2::radians(velocity.0.to_angle());
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GravityField>()
+            .init_resource::<SimulationSettings>()
+            .init_resource::<OnRails>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<SimulationTime>()
+            .add_systems(FixedFirst, count_tick)
+            .add_systems(
+                FixedUpdate,
+                (integrate, align_to_velocity).chain().in_set(KinematicsSet),
+            );
+    }
+}
@@ -1,86 +1,120 @@
-pub mod transform2d {
-    use bevy::prelude::*;
-
-    #[derive(Debug, Clone, Copy, PartialEq, Component)]
-    pub struct Transform2d {
-        pub translation: Vec2,
-        pub z_layer: f32,
-        pub rotation: Rot2,
-        pub scale: Vec2,
-    }
+pub mod settings;
+pub use settings::SimulationSettings;
 
-    impl Default for Transform2d {
-        fn default() -> Self {
-            Self {
-                translation: Vec2::ZERO,
-                z_layer: 0.0,
-                rotation: Rot2::IDENTITY,
-                scale: Vec2::ONE,
-            }
-        }
-    }
+pub mod transform2d;
+pub use transform2d::{GlobalTransform2d, Transform2d};
 
-    impl Transform2d {
-        pub fn with_translation(mut self, value: Vec2) -> Self {
-            self.translation = value;
-            self
-        }
+pub mod trajectory;
+pub use trajectory::{Trajectory, TrajectoryNode};
 
-        pub fn with_z_layer(mut self, value: f32) -> Self {
-            self.z_layer = value;
-            self
-        }
+pub mod gravity;
+pub use gravity::{GravityField, Mass};
 
-        pub fn with_rotation(mut self, value: Rot2) -> Self {
-            self.rotation = value;
-            self
-        }
+pub mod float;
 
-        pub fn with_scale(mut self, value: Vec2) -> Self {
-            self.scale = value;
-            self
-        }
+pub mod kepler;
 
-        pub fn local_y(&self) -> Dir2 {
-            // Rot2 * unit vector is length 1
-            Dir2::new_unchecked(self.rotation * Vec2::Y)
-        }
+pub mod kinematics;
+pub use kinematics::Velocity;
 
-        pub fn local_x(&self) -> Dir2 {
-            // Rot2 * unit vector is length 1
-            Dir2::new_unchecked(self.rotation * Vec2::X)
-        }
-    }
+pub mod conservation;
 
-    fn transform2d_to_bevy_transform(
-        mut query: Query<(&Transform2d, &mut Transform), Changed<Transform2d>>,
-    ) {
-        for (transform2d, mut bevy_transform) in query.iter_mut() {
-            bevy_transform.translation = transform2d.translation.extend(transform2d.z_layer);
-            bevy_transform.rotation = Quat::from_rotation_z(transform2d.rotation.as_radians());
-            bevy_transform.scale = transform2d.scale.extend(1.0);
-        }
-    }
+pub mod pause;
 
-    pub struct Plugin;
+pub mod input;
 
-    impl bevy::prelude::Plugin for Plugin {
-        fn build(&self, app: &mut App) {
-            app.add_systems(PostStartup, transform2d_to_bevy_transform)
-                .add_systems(FixedPostUpdate, transform2d_to_bevy_transform);
-        }
-    }
-}
-pub use transform2d::Transform2d;
+pub mod rng;
 
-pub mod trajectory;
-pub use trajectory::{Trajectory, TrajectoryNode};
+pub mod rewind;
 
-pub mod gravity;
-pub use gravity::{GravityField, Mass};
+pub mod replay;
+pub use replay::Replay;
+
+pub mod net;
+
+pub mod save;
+pub use save::SaveGame;
+
+pub mod warp;
+pub use warp::TimeWarp;
+
+pub mod star;
+pub use star::Star;
+
+pub mod ship;
+pub use ship::Ship;
+
+pub mod missile;
+pub use missile::Missile;
+
+pub mod mine;
+
+pub mod beam;
+
+pub mod fragment;
+
+pub mod turret;
+
+pub mod weapon;
+
+pub mod debug_info;
+
+pub mod keybinds;
+pub use keybinds::{KeyBinds, KeyPair};
+
+pub mod scenario;
+pub use scenario::Scenario;
+
+pub mod generator;
+
+pub mod game;
+pub use game::SpacewarPlugins;
+
+#[cfg(feature = "headless")]
+pub mod headless;
+#[cfg(feature = "headless")]
+pub use headless::HeadlessPlugins;
+
+type Transform = Transform2d;
+
+/// Whether anything is going to be drawn, as opposed to running headless. Plugins only set up
+/// meshes, materials and gizmos when this is true.
+pub fn has_renderer(app: &bevy::app::App) -> bool {
+    app.is_plugin_added::<bevy::render::RenderPlugin>()
+}
+
+use bevy::math::{Dir2, Vec2, ops};
 
 pub fn smoothstep(x: f32) -> f32 {
-    3.0 * x.powi(2) - 2.0 * x.powi(3)
+    3.0 * x * x - 2.0 * x * x * x
+}
+
+/// Distance along a ray to where it first enters a circle, if it ever does.
+///
+/// A ray starting inside the circle hits it immediately, at distance `0.0`.
+pub fn ray_circle_intersection(
+    origin: Vec2,
+    direction: Dir2,
+    center: Vec2,
+    radius: f32,
+) -> Option<f32> {
+    let to_center = center - origin;
+    let radius_squared = radius * radius;
+    if to_center.length_squared() <= radius_squared {
+        return Some(0.0);
+    }
+
+    let closest_approach = to_center.dot(*direction);
+    if closest_approach < 0.0 {
+        return None;
+    }
+
+    let miss_distance_squared = to_center.length_squared() - closest_approach * closest_approach;
+    if miss_distance_squared > radius_squared {
+        return None;
+    }
+
+    Some(closest_approach - ops::sqrt(radius_squared - miss_distance_squared))
 }
 
 pub fn rk4(y: impl Fn(f32) -> f32, t0: f32, h: f32) -> f32 {
@@ -121,4 +155,25 @@ mod tests {
 
         assert!(error < 0.01);
     }
+
+    #[test]
+    fn ray_circle_intersection() {
+        use super::ray_circle_intersection;
+        use bevy::math::{Dir2, Vec2};
+
+        let hit = ray_circle_intersection(Vec2::ZERO, Dir2::X, Vec2::new(10.0, 0.0), 2.0);
+        assert_eq!(hit, Some(8.0));
+
+        let grazing = ray_circle_intersection(Vec2::ZERO, Dir2::X, Vec2::new(10.0, 2.0), 2.0);
+        assert_eq!(grazing, Some(10.0));
+
+        let miss = ray_circle_intersection(Vec2::ZERO, Dir2::X, Vec2::new(10.0, 3.0), 2.0);
+        assert_eq!(miss, None);
+
+        let behind = ray_circle_intersection(Vec2::ZERO, Dir2::X, Vec2::new(-10.0, 0.0), 2.0);
+        assert_eq!(behind, None);
+
+        let inside = ray_circle_intersection(Vec2::ZERO, Dir2::X, Vec2::new(1.0, 0.0), 2.0);
+        assert_eq!(inside, Some(0.0));
+    }
 }
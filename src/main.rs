@@ -2,92 +2,304 @@
 #[allow(unused_imports, clippy::single_component_path_imports)]
 use bevy_dylib;
 
-use std::ops::DerefMut;
-
-use bevy::prelude::*;
-use spacewar::*;
-
-mod star;
-use star::Star;
-
-mod ship;
-use ship::Ship;
-
-mod missile;
-use missile::Missile;
-
-type Transform = Transform2d;
-
-mod debug_info;
-
-mod keybinds;
-pub use keybinds::{KeyBinds, KeyPair};
-
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(bevy::diagnostic::FrameTimeDiagnosticsPlugin::default())
-        .add_plugins(transform2d::Plugin)
-        .add_plugins(star::Plugin)
-        .add_plugins(ship::Plugin)
-        .add_plugins(missile::Plugin)
-        .add_plugins(debug_info::Plugin)
-        .init_resource::<GravityField>()
-        .insert_resource(KeyBinds::default())
-        .add_systems(Startup, (startup, respawn_ship))
-        .add_systems(
-            Update,
-            (
-                respawn_ship.run_if(|keys: Res<ButtonInput<KeyCode>>, keybinds: Res<KeyBinds>| {
-                    keys.any_just_pressed(keybinds.reset())
-                }),
-                zoom,
-            ),
-        )
-        .run();
-}
+use bevy::{
+    app::PluginGroupBuilder,
+    prelude::*,
+    window::{WindowMode, WindowResolution},
+};
+use clap::{CommandFactory, Parser, ValueEnum, error::ErrorKind};
+use spacewar::{
+    Replay, SaveGame, Scenario, SpacewarPlugins, debug_info,
+    generator::Generator,
+    net::{self, NetSettings, Role, Session},
+    replay, save, scenario, settings,
+};
+use std::{net::SocketAddr, path::PathBuf, process::ExitCode};
+#[cfg(feature = "headless")]
+use {
+    bevy::log::LogPlugin,
+    spacewar::{
+        HeadlessPlugins, headless,
+        replay::{Playback, Recording},
+    },
+};
 
-fn startup(mut commands: Commands) {
-    commands.spawn(Camera2d);
+/// Spacewar, with Newtonian gravity.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// Scenario to play, as a path under `assets`, like `scenarios/binary.scenario.ron`
+    #[arg(long, value_name = "ASSET")]
+    scenario: Option<String>,
 
-    commands.spawn(star::Bundle {
-        star: Star { mass: 1.6e16 },
-        transform: Transform::default(),
-    });
+    /// Play in a star system made up from SEED, in place of a scenario
+    #[arg(long, value_name = "SEED", conflicts_with_all = ["scenario", "play", "load"])]
+    generate: Option<u64>,
+
+    /// Seed for the simulation's random numbers, in place of the scenario's own
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Window width, in logical pixels
+    #[arg(long, default_value_t = 1280, value_parser = clap::value_parser!(u32).range(1..))]
+    width: u32,
+
+    /// Window height, in logical pixels
+    #[arg(long, default_value_t = 720, value_parser = clap::value_parser!(u32).range(1..))]
+    height: u32,
+
+    /// Fill the whole screen instead of opening a window
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Fixed ticks per second, in place of the settings file's
+    #[arg(long, value_name = "HZ", value_parser = tick_rate)]
+    tick_rate: Option<f64>,
+
+    #[cfg(feature = "headless")]
+    /// Run this many ticks without a window, as fast as possible, then exit
+    #[arg(
+        long,
+        value_name = "TICKS",
+        conflicts_with_all = ["width", "height", "fullscreen", "host", "join"],
+    )]
+    headless: Option<u32>,
+
+    /// Record the match, saving it to FILE on exit
+    #[arg(long, value_name = "FILE", conflicts_with = "play")]
+    record: Option<PathBuf>,
+
+    /// Play back a recorded match, which brings its own scenario and settings
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["scenario", "seed", "tick_rate"])]
+    play: Option<PathBuf>,
+
+    /// Carry on from a saved match, which brings its own scenario
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["scenario", "seed", "record", "play", "host", "join"],
+    )]
+    load: Option<PathBuf>,
+
+    /// Host a two-player match, listening on ADDRESS
+    #[arg(long, value_name = "ADDRESS", conflicts_with = "join")]
+    host: Option<SocketAddr>,
+
+    /// Join a two-player match hosted at ADDRESS
+    #[arg(long, value_name = "ADDRESS")]
+    join: Option<SocketAddr>,
 
-    /*     commands.spawn(star::Bundle {
-        star: Star { mass: 200_000.0 },
-        transform: Transform::default().with_translation(-Vec2::X * 500.0),
-    }); */
+    /// Whether the debug overlay starts out shown
+    #[arg(long, value_enum, default_value_t = Overlay::Shown)]
+    debug_overlay: Overlay,
 }
 
-fn respawn_ship(mut commands: Commands, ship: Option<Single<Entity, With<Ship>>>) {
-    if let Some(ship) = ship {
-        commands.entity(*ship).despawn();
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Overlay {
+    Shown,
+    Hidden,
+}
+
+fn tick_rate(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        Ok(_) => Err("has to be more than 0".into()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+impl Args {
+    /// The scenario to start with, unless a file or replay brings its own.
+    fn scenario(&self) -> Scenario {
+        self.generate
+            .map(|seed| Generator::default().generate(seed))
+            .unwrap_or_default()
+    }
+
+    /// Either the game's plugins or the headless ones, set up as asked.
+    fn configure(
+        &self,
+        plugins: PluginGroupBuilder,
+        mode: replay::Mode,
+        load: Option<SaveGame>,
+    ) -> PluginGroupBuilder {
+        plugins
+            .set(settings::Plugin {
+                tick_rate: self.tick_rate,
+                ..Default::default()
+            })
+            .set(scenario::Plugin {
+                path: self.scenario.clone(),
+                seed: self.seed,
+            })
+            .set(replay::Plugin {
+                mode,
+                ..Default::default()
+            })
+            .set(save::Plugin {
+                load: load.map(Box::new),
+                ..Default::default()
+            })
     }
 
-    commands.spawn(ship::Bundle {
-        transform: Transform::default().with_translation(Vec2::new(-1000.0, 500.0)),
-        ship: Ship {
-            sas: None,
+    fn debug_info(&self) -> debug_info::Plugin {
+        debug_info::Plugin {
+            state: match self.debug_overlay {
+                Overlay::Shown => debug_info::State::Shown,
+                Overlay::Hidden => debug_info::State::Hidden,
+            },
+        }
+    }
+
+    fn window(&self) -> Window {
+        Window {
+            title: "Spacewar".into(),
+            resolution: WindowResolution::new(self.width as f32, self.height as f32),
+            mode: if self.fullscreen {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+            } else {
+                WindowMode::Windowed
+            },
             ..Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "headless")]
+/// Reports how a headless run went, saving its recording if there is one.
+fn finish_headless(mut app: App) -> ExitCode {
+    if let Some(AppExit::Error(_)) = app.should_exit() {
+        return ExitCode::FAILURE;
+    }
+
+    let world = app.world_mut();
+    for line in headless::ship_summary(world) {
+        println!("{line}");
+    }
+
+    if let Some(recording) = world.get_resource::<Recording>()
+        && let Some(path) = &recording.path
+        && let Err(err) = recording.replay.save(path)
+    {
+        eprintln!("{err}");
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(desync) = world.get_resource::<Playback>().and_then(|p| p.desync) {
+        eprintln!(
+            "replay went out of sync at tick {}: expected checksum {:#x}, got {:#x}",
+            desync.tick, desync.expected, desync.actual
+        );
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let mode = match (&args.record, &args.play) {
+        (Some(path), _) => replay::Mode::Record(Some(path.clone())),
+        (_, Some(path)) => match Replay::load(path) {
+            Ok(replay) => replay::Mode::Play(Box::new(replay)),
+            Err(err) => Args::command().error(ErrorKind::InvalidValue, err).exit(),
         },
+        _ => replay::Mode::Off,
+    };
+
+    let load = args.load.as_ref().map(|path| {
+        SaveGame::load(path).unwrap_or_else(|err| {
+            Args::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!("couldn't load {}: {err}", path.display()),
+                )
+                .exit()
+        })
     });
-}
 
-fn zoom(
-    mut camera: Single<&mut Projection, With<Camera2d>>,
-    keys: Res<ButtonInput<KeyCode>>,
-    keybinds: Res<KeyBinds>,
-) {
-    let projection = match **camera.deref_mut() {
-        Projection::Orthographic(ref mut projection) => projection,
-        _ => unimplemented!(),
+    let mut app = App::new();
+
+    #[cfg(feature = "headless")]
+    if let Some(ticks) = args.headless {
+        app.add_plugins(LogPlugin::default()).add_plugins(
+            args.configure(
+                HeadlessPlugins {
+                    scenario: args.scenario(),
+                    ..Default::default()
+                }
+                .build(),
+                mode,
+                load,
+            ),
+        );
+        headless::run_ticks(&mut app, ticks);
+        return finish_headless(app);
+    }
+
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(args.window()),
+        ..Default::default()
+    }))
+    .add_plugins(
+        args.configure(
+            SpacewarPlugins {
+                scenario: args.scenario(),
+                ..Default::default()
+            }
+            .build(),
+            mode,
+            load,
+        )
+        .set(args.debug_info()),
+    );
+
+    let role = match (args.host, args.join) {
+        (Some(address), _) => Some(Role::Host(address)),
+        (_, Some(address)) => Some(Role::Join(address)),
+        _ => None,
     };
+    if let Some(role) = role {
+        match Session::new(role, NetSettings::default()) {
+            Ok(session) => app.add_plugins(net::Plugin::new(session)),
+            Err(err) => {
+                eprintln!("couldn't open a socket: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+    }
+
+    match app.run() {
+        AppExit::Success => ExitCode::SUCCESS,
+        AppExit::Error(_) => ExitCode::FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_are_consistent() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn refuses_conflicting_flags() {
+        let err = Args::try_parse_from(["spacewar", "--play", "a.ron", "--seed", "1"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+
+        let err = Args::try_parse_from(["spacewar", "--generate", "1", "--scenario", "a"]);
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::ArgumentConflict);
+
+        let args = Args::try_parse_from(["spacewar", "--tick-rate", "32"]);
+        assert_eq!(args.unwrap().tick_rate, Some(32.0));
 
-    if keys.any_pressed(keybinds.zoom().map(KeyPair::left)) {
-        projection.scale *= 1.025
-    } else if keys.any_pressed(keybinds.zoom().map(KeyPair::right)) {
-        projection.scale *= 0.975
+        #[cfg(feature = "headless")]
+        {
+            let err = Args::try_parse_from(["spacewar", "--headless", "64", "--fullscreen"]);
+            assert_eq!(err.unwrap_err().kind(), ErrorKind::ArgumentConflict);
+        }
     }
 }
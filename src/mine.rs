@@ -0,0 +1,131 @@
+use crate::{
+    Ship, Transform, Velocity,
+    kinematics::KinematicsSet,
+    weapon::{Hit, Owner, Weapon},
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// seconds after deployment before a mine can go off
+const ARM_DELAY: f32 = 3.0;
+
+/// distance from a ship at which an armed mine detonates
+const TRIGGER_RADIUS: f32 = 40.0;
+
+const DAMAGE: f32 = 50.0;
+
+#[derive(Clone, Debug, PartialEq, Component, Default, TypePath, Serialize, Deserialize)]
+pub struct Mine {
+    pub age: f32,
+}
+
+impl Mine {
+    pub fn is_armed(&self) -> bool {
+        self.age >= ARM_DELAY
+    }
+}
+
+impl Weapon for Mine {
+    const MAX_AGE: f32 = 120.0;
+    const MAX_PER_SHIP: usize = 5;
+
+    fn age(&self) -> f32 {
+        self.age
+    }
+
+    fn age_mut(&mut self) -> &mut f32 {
+        &mut self.age
+    }
+
+    fn color(&self) -> Color {
+        if self.is_armed() {
+            Color::oklch(0.7, 0.2, 25.0)
+        } else {
+            Color::oklch(0.9, 0.15, 90.0)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Bundle, Default)]
+pub struct Bundle {
+    pub mine: Mine,
+    pub transform: Transform,
+    pub velocity: Velocity,
+}
+
+#[derive(Clone, Debug, Resource, Bundle)]
+struct Sprite {
+    mesh: Mesh2d,
+    material: MeshMaterial2d<ColorMaterial>,
+}
+
+impl FromWorld for Sprite {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh: Mesh2d(world.add_asset(Rhombus::new(1.0, 1.0))),
+            material: MeshMaterial2d(world.add_asset(Color::WHITE)),
+        }
+    }
+}
+
+fn spawn(
+    mut commands: Commands,
+    mines: Query<Entity, Added<Mine>>,
+    mut components: Query<&mut Transform>,
+    sprite: Res<Sprite>,
+) {
+    for entity in mines.iter() {
+        let mut transform = components.get_mut(entity).unwrap();
+
+        transform.scale = Vec2::splat(12.0);
+
+        commands.entity(entity).insert(sprite.clone());
+    }
+}
+
+fn detonate(
+    mut commands: Commands,
+    mines: Query<(Entity, &Mine, &Transform, Option<&Owner>)>,
+    ships: Query<(Entity, &Transform), With<Ship>>,
+    mut hits: EventWriter<Hit>,
+) {
+    for (id, mine, mine_transform, owner) in mines.iter() {
+        if !mine.is_armed() {
+            continue;
+        }
+
+        // the deploying ship can't set off its own mines, but it isn't safe from the blast
+        let triggered = ships.iter().any(|(ship, transform)| {
+            owner.is_none_or(|owner| owner.0 != ship)
+                && transform.translation.distance(mine_transform.translation) < TRIGGER_RADIUS
+        });
+
+        if !triggered {
+            continue;
+        }
+
+        for (ship, transform) in ships.iter() {
+            if transform.translation.distance(mine_transform.translation) < TRIGGER_RADIUS {
+                hits.write(Hit {
+                    target: ship,
+                    damage: DAMAGE,
+                });
+            }
+        }
+
+        commands.entity(id).despawn();
+    }
+}
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(crate::weapon::Plugin::<Mine>::default())
+            .add_systems(FixedUpdate, detonate.after(KinematicsSet));
+
+        if crate::has_renderer(app) {
+            app.init_resource::<Sprite>().add_systems(PostUpdate, spawn);
+        }
+    }
+}
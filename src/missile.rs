@@ -1,18 +1,47 @@
-use crate::{GravityField, Transform};
+use crate::{
+    Ship, Transform, Velocity,
+    fragment::{self, Fragment},
+    kinematics::KinematicsSet,
+    rng::SimulationRng,
+    smoothstep,
+    weapon::{Hit, Owner, Weapon},
+};
 use bevy::prelude::*;
-use spacewar::TrajectoryNode;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Component)]
+/// damage at the centre of a detonation, falling off to nothing at [`BLAST_RADIUS`]
+const MAX_DAMAGE: f32 = 60.0;
+const BLAST_RADIUS: f32 = 150.0;
+
+const FRAGMENT_COUNT: usize = 8;
+const FRAGMENT_SPREAD: f32 = 40.0;
+const FRAGMENT_DAMAGE: f32 = 10.0;
+
+#[derive(Clone, Debug, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
 pub struct Missile {
-    pub speed: f32,
     pub age: f32,
+    /// how close the missile has to get to a ship before it goes off
+    pub fuse_radius: f32,
+}
+
+impl Weapon for Missile {
+    const MAX_AGE: f32 = 30.0;
+
+    fn age(&self) -> f32 {
+        self.age
+    }
+
+    fn age_mut(&mut self) -> &mut f32 {
+        &mut self.age
+    }
 }
 
 impl Default for Missile {
     fn default() -> Self {
         Self {
-            speed: 5.0,
             age: 0.0,
+            fuse_radius: 60.0,
         }
     }
 }
@@ -21,6 +50,7 @@ impl Default for Missile {
 pub struct Bundle {
     pub missile: Missile,
     pub transform: Transform,
+    pub velocity: Velocity,
 }
 
 #[derive(Clone, Debug, Resource, Bundle)]
@@ -53,48 +83,49 @@ fn spawn(
     }
 }
 
-fn update_missile(mut missile: Query<(&mut Missile, &mut Transform)>, gravity: Res<GravityField>) {
-    for (mut missile, mut missile_transform) in missile.iter_mut() {
-        let mut trajectory = gravity.trajectory_starting_at(
-            TrajectoryNode::from_translation_velocity(
-                missile_transform.translation,
-                missile.speed * missile_transform.local_x(),
-            ),
-            1.0,
-        );
-
-        trajectory.mass = 1.0;
-
-        let velocity = trajectory.next_guaranteed().velocity();
-        missile_transform.rotation = Rot2::radians(velocity.to_angle());
-        missile.speed = velocity.length();
-
-        let direction = missile_transform.local_x();
-        missile_transform.translation += missile.speed * direction;
-    }
-}
-
-fn update_age(mut missiles: Query<&mut Missile>, time: Res<Time>) {
-    for mut missile in missiles.iter_mut() {
-        missile.age += time.delta_secs();
-    }
-}
-
-fn aging(
+fn detonate(
     mut commands: Commands,
-    mut missiles: Query<(Entity, &Missile, &mut MeshMaterial2d<ColorMaterial>)>,
-    mut colors: ResMut<Assets<ColorMaterial>>,
+    missiles: Query<(Entity, &Missile, &Transform, &Velocity, Option<&Owner>)>,
+    ships: Query<(Entity, &Transform), With<Ship>>,
+    mut hits: EventWriter<Hit>,
+    mut rng: ResMut<SimulationRng>,
 ) {
-    const MAX_AGE: f32 = 30.0;
+    for (id, missile, missile_transform, velocity, owner) in missiles.iter() {
+        let origin = missile_transform.translation;
+        let fuse_radius = missile.fuse_radius.max(Ship::HIT_RADIUS);
+
+        let triggered = ships.iter().any(|(ship, transform)| {
+            owner.is_none_or(|owner| owner.0 != ship)
+                && transform.translation.distance(origin) < fuse_radius
+        });
 
-    for (id, missile, mut material) in missiles.iter_mut() {
-        if missile.age > MAX_AGE {
-            commands.entity(id).despawn();
+        if !triggered {
             continue;
         }
 
-        *material =
-            MeshMaterial2d(colors.add(Color::WHITE.with_alpha(1.0 - missile.age / MAX_AGE)));
+        for (ship, transform) in ships.iter() {
+            let distance = transform.translation.distance(origin);
+            if distance < BLAST_RADIUS {
+                hits.write(Hit {
+                    target: ship,
+                    damage: MAX_DAMAGE * (1.0 - smoothstep(distance / BLAST_RADIUS)),
+                });
+            }
+        }
+
+        commands.spawn_batch(fragment::scatter(
+            origin,
+            velocity.0,
+            Fragment {
+                damage: FRAGMENT_DAMAGE,
+                ..Default::default()
+            },
+            FRAGMENT_SPREAD,
+            FRAGMENT_COUNT,
+            &mut rng.0,
+        ));
+
+        commands.entity(id).despawn();
     }
 }
 
@@ -102,9 +133,15 @@ pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Sprite>()
-            .add_systems(PostStartup, spawn)
-            .add_systems(FixedUpdate, (update_missile, update_age))
-            .add_systems(PostUpdate, (spawn, aging));
+        app.add_plugins(crate::weapon::Plugin::<Missile>::default())
+            .register_type::<Missile>()
+            .init_resource::<SimulationRng>()
+            .add_systems(FixedUpdate, detonate.after(KinematicsSet));
+
+        if crate::has_renderer(app) {
+            app.init_resource::<Sprite>()
+                .add_systems(PostStartup, spawn)
+                .add_systems(PostUpdate, spawn);
+        }
     }
 }
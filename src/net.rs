@@ -0,0 +1,683 @@
+use crate::{
+    SimulationSettings, TimeWarp,
+    input::{
+        InputSet, Inputs, LocalInput, LocalPlayer, Player, Players, ShipInput, apply_local_input,
+    },
+    kinematics::SimulationTick,
+    pause::PendingSteps,
+    replay::Desync,
+    rewind::{History, Snapshot},
+};
+use bevy::{
+    app::{FixedMain, RunFixedMainLoop, RunFixedMainLoopSystem},
+    prelude::*,
+};
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// Packets from any other version of the protocol are ignored, so mismatched builds never get as
+/// far as playing together.
+pub const PROTOCOL: u8 = 1;
+
+/// A timestep so long that no tick ever comes due, for holding the simulation back.
+const PARKED: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// at most how many of our inputs are resent in one packet
+const MAX_RESEND: usize = 64;
+
+/// Which side of the match this is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// waits at the address for someone to join, then plays as the first player
+    Host(SocketAddr),
+    /// joins the host at the address, playing as the second player
+    Join(SocketAddr),
+}
+
+impl Role {
+    pub fn player(self) -> Player {
+        match self {
+            Role::Host(_) => Player(0),
+            Role::Join(_) => Player(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NetSettings {
+    /// ticks between a key being pressed and it taking effect, giving it time to reach the other
+    /// side before it's needed there
+    pub input_delay: u64,
+    /// how far the simulation may run ahead of the other side's input, guessing at it, before it
+    /// waits for them to catch up
+    pub max_prediction: u64,
+    /// ticks between comparing state hashes with the other side
+    pub check_interval: u64,
+}
+
+impl Default for NetSettings {
+    fn default() -> Self {
+        Self {
+            input_delay: 2,
+            max_prediction: 8,
+            check_interval: 16,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Packet {
+    /// sent by whoever's joining until they're welcomed
+    Hello,
+    Welcome,
+    Inputs {
+        /// every input of the receiver's up to this tick has arrived
+        ack: u64,
+        /// the sender's latest state hash and the tick it's for
+        checksum: Option<(u64, u64)>,
+        /// tick of the first input
+        first: u64,
+        inputs: Vec<ShipInput>,
+    },
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![PROTOCOL];
+        match self {
+            Packet::Hello => bytes.push(0),
+            Packet::Welcome => bytes.push(1),
+            Packet::Inputs {
+                ack,
+                checksum,
+                first,
+                inputs,
+            } => {
+                let (checksum_tick, checksum) = checksum.unwrap_or_default();
+
+                bytes.push(2);
+                for value in [*ack, checksum_tick, checksum, *first] {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                for input in inputs {
+                    bytes.extend_from_slice(&encode_input(*input));
+                }
+            }
+        }
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        fn take_u64(bytes: &mut &[u8]) -> Option<u64> {
+            let (value, rest) = bytes.split_first_chunk::<8>()?;
+            *bytes = rest;
+            Some(u64::from_le_bytes(*value))
+        }
+
+        let ([protocol, kind], mut rest) = bytes.split_first_chunk::<2>()?;
+        if *protocol != PROTOCOL {
+            return None;
+        }
+
+        match kind {
+            0 => Some(Packet::Hello),
+            1 => Some(Packet::Welcome),
+            2 => {
+                let ack = take_u64(&mut rest)?;
+                let checksum_tick = take_u64(&mut rest)?;
+                let checksum = take_u64(&mut rest)?;
+                let first = take_u64(&mut rest)?;
+
+                let (inputs, []) = rest.as_chunks::<2>() else {
+                    return None;
+                };
+
+                Some(Packet::Inputs {
+                    ack,
+                    // nothing is ever checked at tick 0
+                    checksum: (checksum_tick != 0).then_some((checksum_tick, checksum)),
+                    first,
+                    inputs: inputs.iter().map(|input| decode_input(*input)).collect(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn encode_input(input: ShipInput) -> [u8; 2] {
+    let flags = [
+        input.thrust,
+        input.toggle_prograde,
+        input.toggle_sas,
+        input.fire_missile,
+        input.deploy_mine,
+        input.fire_beam,
+        input.respawn,
+    ]
+    .into_iter()
+    .enumerate()
+    .fold(0, |flags, (bit, set)| flags | (u8::from(set) << bit));
+
+    [flags, input.turn as u8]
+}
+
+fn decode_input([flags, turn]: [u8; 2]) -> ShipInput {
+    let bit = |bit: u8| flags & (1 << bit) != 0;
+
+    ShipInput {
+        thrust: bit(0),
+        turn: (turn as i8).clamp(-1, 1),
+        toggle_prograde: bit(1),
+        toggle_sas: bit(2),
+        fire_missile: bit(3),
+        deploy_mine: bit(4),
+        fire_beam: bit(5),
+        respawn: bit(6),
+    }
+}
+
+/// A match against someone on the other end of a UDP socket.
+///
+/// Both sides simulate everything themselves, trading only input. When the other side's input for
+/// a tick hasn't arrived yet it's guessed at, and if the guess turns out wrong the world is rolled
+/// back to before that tick and simulated forward again.
+#[derive(Debug, Resource)]
+pub struct Session {
+    socket: UdpSocket,
+    role: Role,
+    pub settings: NetSettings,
+    peer: Option<SocketAddr>,
+    started: bool,
+    /// ours, by tick
+    local: BTreeMap<u64, ShipInput>,
+    /// theirs, by tick
+    remote: BTreeMap<u64, ShipInput>,
+    /// what was used for their input on ticks it hadn't arrived for yet
+    predicted: BTreeMap<u64, ShipInput>,
+    /// every input of theirs up to this tick has arrived
+    confirmed: u64,
+    /// every input of ours up to this tick has arrived on their side
+    acked: u64,
+    /// the latest tick our input was taken for
+    captured: u64,
+    /// the earliest tick that was simulated with a wrong guess
+    rollback: Option<u64>,
+    next_check: u64,
+    /// ours, waiting on theirs to compare with
+    checksums: BTreeMap<u64, u64>,
+    remote_checksums: BTreeMap<u64, u64>,
+    latest_checksum: Option<(u64, u64)>,
+    /// the first tick at which the two sides were found to have drifted apart
+    pub desync: Option<Desync>,
+    /// how many times a wrong guess has been rolled back
+    pub rollbacks: u64,
+}
+
+impl Session {
+    /// Binds the socket, as the host at its address, or anywhere for joining.
+    pub fn new(role: Role, settings: NetSettings) -> io::Result<Self> {
+        let (bind, peer) = match role {
+            Role::Host(address) => (address, None),
+            Role::Join(host) => {
+                let any = if host.is_ipv4() {
+                    Ipv4Addr::UNSPECIFIED.into()
+                } else {
+                    Ipv6Addr::UNSPECIFIED.into()
+                };
+                (SocketAddr::new(any, 0), Some(host))
+            }
+        };
+
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            role,
+            settings,
+            peer,
+            started: false,
+            // nobody can have pressed anything in time for the first few ticks
+            local: (1..=settings.input_delay)
+                .map(|tick| (tick, ShipInput::default()))
+                .collect(),
+            remote: BTreeMap::new(),
+            predicted: BTreeMap::new(),
+            confirmed: 0,
+            acked: 0,
+            captured: 0,
+            rollback: None,
+            next_check: settings.check_interval.max(1),
+            checksums: BTreeMap::new(),
+            remote_checksums: BTreeMap::new(),
+            latest_checksum: None,
+            desync: None,
+            rollbacks: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    fn remote_player(&self) -> Player {
+        Player(1 - self.role.player().0)
+    }
+
+    /// Whether both sides have found each other, and ticks are running.
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Every input from the other side up to this tick has arrived.
+    pub fn confirmed(&self) -> u64 {
+        self.confirmed
+    }
+
+    /// Whether the tick after `tick` can be simulated yet.
+    fn may_advance(&self, tick: u64) -> bool {
+        self.started && tick < self.confirmed + self.settings.max_prediction
+    }
+
+    fn send(&self, packet: &Packet) {
+        let Some(peer) = self.peer else {
+            return;
+        };
+
+        match self.socket.send_to(&packet.encode(), peer) {
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(err) => warn!("couldn't send to {peer}: {err}"),
+        }
+    }
+
+    /// Takes in everything that's arrived, returning whether the match has just started.
+    fn receive(&mut self) -> bool {
+        let was_started = self.started;
+        let mut buffer = [0; 1024];
+
+        loop {
+            let (len, from) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                // something sent earlier bounced, likely because the other side wasn't up yet
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionReset
+                    ) =>
+                {
+                    continue;
+                }
+                Err(err) => {
+                    warn!("couldn't receive: {err}");
+                    break;
+                }
+            };
+
+            let Some(packet) = Packet::decode(&buffer[..len]) else {
+                continue;
+            };
+
+            match (packet, self.role) {
+                (Packet::Hello, Role::Host(_)) if self.peer.is_none_or(|peer| peer == from) => {
+                    if !self.started {
+                        info!("{from} joined");
+                    }
+                    self.peer = Some(from);
+                    self.started = true;
+                    self.send(&Packet::Welcome);
+                }
+                (Packet::Welcome, Role::Join(host)) if from == host => {
+                    if !self.started {
+                        info!("joined {host}");
+                    }
+                    self.started = true;
+                }
+                (
+                    Packet::Inputs {
+                        ack,
+                        checksum,
+                        first,
+                        inputs,
+                    },
+                    _,
+                ) if self.peer == Some(from) => {
+                    // the welcome might have been lost, but the host has clearly started
+                    self.started = true;
+                    self.receive_inputs(ack, checksum, first, inputs);
+                }
+                _ => {}
+            }
+        }
+
+        self.started && !was_started
+    }
+
+    fn receive_inputs(
+        &mut self,
+        ack: u64,
+        checksum: Option<(u64, u64)>,
+        first: u64,
+        inputs: Vec<ShipInput>,
+    ) {
+        self.acked = self.acked.max(ack);
+        if let Some((tick, checksum)) = checksum {
+            self.remote_checksums.insert(tick, checksum);
+        }
+
+        for (tick, input) in (first..).zip(inputs) {
+            if tick > self.confirmed {
+                self.remote.entry(tick).or_insert(input);
+            }
+        }
+
+        while let Some(&input) = self.remote.get(&(self.confirmed + 1)) {
+            self.confirmed += 1;
+
+            if let Some(predicted) = self.predicted.remove(&self.confirmed)
+                && predicted != input
+            {
+                self.rollback.get_or_insert(self.confirmed);
+            }
+        }
+    }
+
+    /// Their input for `tick`, or a guess at it: that they're still holding down whatever they
+    /// last were.
+    fn remote_input(&mut self, tick: u64) -> ShipInput {
+        if let Some(&input) = self.remote.get(&tick) {
+            return input;
+        }
+
+        let mut guess = self
+            .remote
+            .get(&self.confirmed)
+            .copied()
+            .unwrap_or_default();
+        guess.release_presses();
+
+        self.predicted.insert(tick, guess);
+        guess
+    }
+
+    fn compare_checksums(&mut self) {
+        let Some(&latest) = self.remote_checksums.keys().next_back() else {
+            return;
+        };
+
+        for (tick, theirs) in std::mem::take(&mut self.remote_checksums) {
+            let Some(ours) = self.checksums.get(&tick).copied() else {
+                // not simulated that far yet
+                if tick >= self.next_check {
+                    self.remote_checksums.insert(tick, theirs);
+                }
+                continue;
+            };
+
+            if ours != theirs && self.desync.is_none() {
+                error!("desynced from the other side at tick {tick}");
+                self.desync = Some(Desync {
+                    tick,
+                    expected: theirs,
+                    actual: ours,
+                });
+            }
+        }
+
+        // anything older has been compared, or is never coming
+        self.checksums = self.checksums.split_off(&(latest + 1));
+    }
+}
+
+/// Takes in whatever's arrived, rolls back and resimulates if any guess at the other side's input
+/// was wrong, and decides whether this frame's ticks can go ahead.
+fn synchronize(world: &mut World) {
+    let tick = world.resource::<SimulationTick>().0;
+
+    if world.resource_mut::<Session>().receive() {
+        // somewhere to roll back to should their very first input be guessed wrong
+        let snapshot = Snapshot::capture(world);
+        let max_size = world.resource::<SimulationSettings>().rewind_memory;
+        world.resource_mut::<History>().push(snapshot, max_size);
+    }
+
+    if let Some(from) = world.resource_mut::<Session>().rollback.take() {
+        roll_back(world, from, tick);
+    }
+
+    check(world);
+
+    // stepping would run ahead of the other side all the same
+    if let Some(mut pending) = world.get_resource_mut::<PendingSteps>() {
+        pending.0 = 0;
+    }
+
+    let timestep = if world.resource::<Session>().may_advance(tick) {
+        world.resource::<SimulationSettings>().timestep()
+    } else {
+        PARKED
+    };
+    world.resource_mut::<Time<Fixed>>().set_timestep(timestep);
+}
+
+/// Resimulates from `from` up to `to`, now that more of the other side's input has arrived.
+fn roll_back(world: &mut World, from: u64, to: u64) {
+    let rewound =
+        world.resource_scope(|world, mut history: Mut<History>| history.rewind_to(from - 1, world));
+    if !rewound {
+        error!("couldn't roll back to tick {}", from - 1);
+        return;
+    }
+
+    world.resource_mut::<Session>().rollbacks += 1;
+    while world.resource::<SimulationTick>().0 < to {
+        *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+        world.run_schedule(FixedMain);
+    }
+
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
+
+/// Hashes every tick due a check that no rollback can change any more, and compares with the
+/// other side's.
+fn check(world: &mut World) {
+    let tick = world.resource::<SimulationTick>().0;
+
+    world.resource_scope(|world, mut session: Mut<Session>| {
+        let history = world.resource::<History>();
+        let settled = session.confirmed.min(tick);
+
+        while session.next_check <= settled {
+            let check = session.next_check;
+            session.next_check += session.settings.check_interval.max(1);
+
+            if let Some(snapshot) = history.get(check) {
+                let checksum = snapshot.checksum();
+                session.checksums.insert(check, checksum);
+                session.latest_checksum = Some((check, checksum));
+            }
+        }
+
+        session.compare_checksums();
+    });
+}
+
+fn apply_inputs(
+    mut session: ResMut<Session>,
+    tick: Res<SimulationTick>,
+    mut local: ResMut<LocalInput>,
+    mut inputs: ResMut<Inputs>,
+) {
+    let tick = tick.0;
+
+    // resimulated ticks keep the input they were first given
+    if tick > session.captured {
+        let delay = session.settings.input_delay;
+        session.captured = tick;
+        session.local.insert(tick + delay, local.take());
+    }
+
+    let ours = session.local.get(&tick).copied().unwrap_or_default();
+    let theirs = session.remote_input(tick);
+
+    *inputs.get_mut(session.role.player()) = ours;
+    let remote = session.remote_player();
+    *inputs.get_mut(remote) = theirs;
+}
+
+/// Stops the frame's ticks short once the simulation is as far ahead as it may get.
+fn hold_back(session: Res<Session>, tick: Res<SimulationTick>, mut fixed: ResMut<Time<Fixed>>) {
+    if !session.may_advance(tick.0) {
+        fixed.set_timestep(PARKED);
+    }
+}
+
+/// Puts the timestep back after holding back, dropping the time that was waited out.
+fn release(mut fixed: ResMut<Time<Fixed>>, settings: Res<SimulationSettings>) {
+    if fixed.timestep() != PARKED {
+        return;
+    }
+
+    let timestep = settings.timestep();
+    fixed.set_timestep(timestep);
+
+    let waited = fixed.overstep().as_nanos() / timestep.as_nanos();
+    fixed.discard_overstep(timestep * waited as u32);
+}
+
+fn send_inputs(mut session: ResMut<Session>, tick: Res<SimulationTick>) {
+    if !session.started {
+        if let Role::Join(_) = session.role {
+            session.send(&Packet::Hello);
+        }
+        return;
+    }
+
+    // ours are kept while they could still be simulated, or haven't reached them yet, and theirs
+    // while they could still be simulated, along with the latest confirmed one to guess from
+    let keep_from = session.acked.min(session.confirmed).min(tick.0) + 1;
+    session.local = session.local.split_off(&keep_from);
+    let keep_from = session.confirmed.min(tick.0 + 1);
+    session.remote = session.remote.split_off(&keep_from);
+
+    let first = session.acked + 1;
+    let inputs = session
+        .local
+        .range(first..)
+        .take(MAX_RESEND)
+        .map(|(_, input)| *input)
+        .collect();
+
+    session.send(&Packet::Inputs {
+        ack: session.confirmed,
+        checksum: session.latest_checksum,
+        first,
+        inputs,
+    });
+}
+
+/// There's no warping when the other side has to keep up.
+fn hold_warp(mut warp: ResMut<TimeWarp>) {
+    warp.set_if_neq(TimeWarp::default());
+}
+
+/// Plays a match against someone else over the network, as set up by a [`Session`].
+///
+/// Needs [`rewind::Plugin`](crate::rewind::Plugin) to roll back with.
+pub struct Plugin {
+    session: Mutex<Option<Session>>,
+}
+
+impl Plugin {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session: Mutex::new(Some(session)),
+        }
+    }
+}
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let session = self
+            .session
+            .lock()
+            .unwrap()
+            .take()
+            .expect("net::Plugin can only be built once");
+
+        app.insert_resource(Players(2))
+            .insert_resource(LocalPlayer(session.role.player()))
+            .insert_resource(LocalInput {
+                direct: false,
+                ..Default::default()
+            })
+            .insert_resource(session)
+            .init_resource::<Inputs>()
+            .init_resource::<History>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<SimulationSettings>()
+            .init_resource::<TimeWarp>()
+            .add_systems(First, hold_warp.before(crate::warp::apply))
+            .add_systems(
+                RunFixedMainLoop,
+                (
+                    synchronize.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+                    (release, send_inputs).in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+                ),
+            )
+            .add_systems(
+                FixedPreUpdate,
+                apply_inputs.in_set(InputSet).after(apply_local_input),
+            )
+            .add_systems(FixedLast, hold_back);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_survive_encoding() {
+        let packets = [
+            Packet::Hello,
+            Packet::Welcome,
+            Packet::Inputs {
+                ack: 41,
+                checksum: Some((32, 0xdead_beef_cafe_f00d)),
+                first: 40,
+                inputs: vec![
+                    ShipInput {
+                        thrust: true,
+                        turn: -1,
+                        ..Default::default()
+                    },
+                    ShipInput {
+                        turn: 1,
+                        fire_missile: true,
+                        respawn: true,
+                        ..Default::default()
+                    },
+                ],
+            },
+        ];
+
+        for packet in packets {
+            assert_eq!(Packet::decode(&packet.encode()), Some(packet));
+        }
+
+        assert_eq!(Packet::decode(&[PROTOCOL + 1, 0]), None);
+        assert_eq!(Packet::decode(&[PROTOCOL, 2, 1, 2, 3]), None);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::KeyBinds;
+use bevy::{
+    app::{FixedMain, RunFixedMainLoop, RunFixedMainLoopSystem},
+    input::InputSystem,
+    prelude::*,
+};
+
+/// how many ticks the step key advances with shift held
+pub const MANY_STEPS: u32 = 10;
+
+/// Fixed ticks still to run while paused, all at once on the next frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub struct PendingSteps(pub u32);
+
+/// Run condition for anything that shouldn't happen while the simulation is paused.
+pub fn is_paused(time: Res<Time<Virtual>>) -> bool {
+    time.is_paused()
+}
+
+fn toggle_pause(
+    mut time: ResMut<Time<Virtual>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<KeyBinds>,
+) {
+    if !keys.any_just_pressed(keybinds.pause()) {
+        return;
+    }
+
+    if time.is_paused() {
+        time.unpause();
+    } else {
+        time.pause();
+    }
+}
+
+fn queue_steps(
+    mut pending: ResMut<PendingSteps>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<KeyBinds>,
+) {
+    if keys.any_just_pressed(keybinds.step()) {
+        pending.0 += if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            MANY_STEPS
+        } else {
+            1
+        };
+    }
+}
+
+/// Runs [`PendingSteps`] the same way the fixed main loop would have, had time not stood still.
+fn run_pending_steps(world: &mut World) {
+    let steps = std::mem::take(&mut world.resource_mut::<PendingSteps>().0);
+    if steps == 0 {
+        return;
+    }
+
+    for _ in 0..steps {
+        let mut fixed = world.resource_mut::<Time<Fixed>>();
+        let timestep = fixed.timestep();
+        fixed.advance_by(timestep);
+
+        *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+        world.run_schedule(FixedMain);
+    }
+
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
+
+#[derive(Clone, Copy, Debug, Component)]
+struct PauseIndicator;
+
+fn spawn_indicator(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        PauseIndicator,
+        Text::new("paused"),
+        TextFont {
+            font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+            font_size: 24.0,
+            ..Default::default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(15.0),
+            ..Default::default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+fn update_indicator(
+    mut indicator: Single<&mut Visibility, With<PauseIndicator>>,
+    time: Res<Time<Virtual>>,
+) {
+    **indicator = if time.is_paused() {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+}
+
+/// Freezes the simulation on demand while everything else keeps running, and steps through it a
+/// tick at a time.
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingSteps>()
+            .add_systems(
+                PreUpdate,
+                (toggle_pause, queue_steps.run_if(is_paused)).after(InputSystem),
+            )
+            .add_systems(
+                RunFixedMainLoop,
+                run_pending_steps.in_set(RunFixedMainLoopSystem::FixedMainLoop),
+            );
+
+        if crate::has_renderer(app) {
+            app.add_systems(Startup, spawn_indicator)
+                .add_systems(PostUpdate, update_indicator);
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use crate::{
+    Scenario, SimulationSettings, TimeWarp,
+    input::{InputSet, Inputs, ShipInput, apply_local_input},
+    kinematics::{OnRails, SimulationTick},
+    rewind::Snapshot,
+    scenario::ScenarioFile,
+    settings::SettingsFile,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path, path::PathBuf};
+
+/// Replays recorded in any other version of the format are refused rather than played wrong.
+pub const VERSION: u32 = 1;
+
+/// ticks between checksums, unless told otherwise
+pub const CHECK_INTERVAL: u64 = 64;
+
+/// Everything that went into one fixed tick that didn't follow from the tick before.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    /// by player
+    pub inputs: Vec<ShipInput>,
+    /// [`TimeWarp`] level, which decides how much time the tick covered
+    pub warp: usize,
+}
+
+/// A whole match, as how it started and everything done since: enough to play it out again
+/// exactly.
+///
+/// Settings are taken as they were when recording started, so changing them in the middle of a
+/// match makes for a replay that won't play back properly.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub scenario: Scenario,
+    pub settings: SimulationSettings,
+    /// every tick from the first on
+    pub ticks: Vec<Tick>,
+    pub check_interval: u64,
+    /// [`Snapshot::checksum`] of the world after every `check_interval`th tick
+    pub checksums: BTreeMap<u64, u64>,
+}
+
+impl Replay {
+    pub fn new(scenario: Scenario, settings: SimulationSettings, check_interval: u64) -> Self {
+        Self {
+            version: VERSION,
+            scenario,
+            settings,
+            ticks: Vec::new(),
+            check_interval,
+            checksums: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Header {
+            version: u32,
+        }
+
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+
+        // checked first, since a replay in another format likely won't parse at all
+        let header: Header = ron::from_str(&text)
+            .map_err(|err| format!("couldn't parse {}: {err}", path.display()))?;
+        if header.version != VERSION {
+            return Err(format!(
+                "{} is a version {} replay, but only version {VERSION} can be played",
+                path.display(),
+                header.version
+            ));
+        }
+
+        ron::from_str(&text).map_err(|err| format!("couldn't parse {}: {err}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| format!("couldn't serialize replay: {err}"))?;
+        std::fs::write(path, text)
+            .map_err(|err| format!("couldn't write {}: {err}", path.display()))
+    }
+
+    /// The given tick, counting from 1 like [`SimulationTick`].
+    pub fn tick(&self, tick: u64) -> Option<&Tick> {
+        self.ticks.get(usize::try_from(tick.checked_sub(1)?).ok()?)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.ticks.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+}
+
+/// The match so far, while it's being recorded.
+#[derive(Debug, Resource)]
+pub struct Recording {
+    pub replay: Replay,
+    /// where it's saved on exit
+    pub path: Option<PathBuf>,
+}
+
+/// A replay being played back, in place of anyone's input.
+#[derive(Debug, Resource)]
+pub struct Playback {
+    pub replay: Replay,
+    /// the first point at which the world didn't match the recording
+    pub desync: Option<Desync>,
+}
+
+impl Playback {
+    pub fn is_finished(&self, tick: SimulationTick) -> bool {
+        tick.0 >= self.replay.len()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Desync {
+    pub tick: u64,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+/// Picks up the scenario once there is one, in case it came from a file.
+fn record_scenario(mut recording: ResMut<Recording>, scenario: Res<Scenario>) {
+    recording.replay.scenario = scenario.clone();
+}
+
+fn record_tick(
+    mut recording: ResMut<Recording>,
+    tick: Res<SimulationTick>,
+    inputs: Res<Inputs>,
+    warp: Res<TimeWarp>,
+) {
+    let replay = &mut recording.replay;
+
+    // anything after this tick was rewound over
+    replay.ticks.truncate(tick.0.saturating_sub(1) as usize);
+    replay.checksums.split_off(&tick.0);
+
+    replay.ticks.push(Tick {
+        inputs: inputs.0.clone(),
+        warp: warp.level(),
+    });
+}
+
+fn record_checksum(world: &mut World) {
+    let tick = world.resource::<SimulationTick>().0;
+    let interval = world.resource::<Recording>().replay.check_interval;
+    if !tick.is_multiple_of(interval) {
+        return;
+    }
+
+    let checksum = Snapshot::capture(world).checksum();
+    world
+        .resource_mut::<Recording>()
+        .replay
+        .checksums
+        .insert(tick, checksum);
+}
+
+fn save_recording(mut exits: EventReader<AppExit>, recording: Res<Recording>) {
+    if exits.read().next().is_none() {
+        return;
+    }
+
+    if let Some(path) = &recording.path {
+        match recording.replay.save(path) {
+            Ok(()) => info!("saved replay to {}", path.display()),
+            Err(err) => error!("{err}"),
+        }
+    }
+}
+
+fn play_inputs(playback: Res<Playback>, tick: Res<SimulationTick>, mut inputs: ResMut<Inputs>) {
+    if let Some(tick) = playback.replay.tick(tick.0) {
+        inputs.0.clone_from(&tick.inputs);
+    }
+}
+
+/// Sets the warp level for the coming tick, both before the frame's ticks start and between them,
+/// since how long each tick is has to match the recording too.
+fn steer_warp(
+    playback: Res<Playback>,
+    tick: Res<SimulationTick>,
+    settings: Res<SimulationSettings>,
+    mut warp: ResMut<TimeWarp>,
+    mut fixed: ResMut<Time<Fixed>>,
+    mut on_rails: ResMut<OnRails>,
+) {
+    let Some(next) = playback.replay.tick(tick.0 + 1) else {
+        return;
+    };
+
+    let mut steered = *warp;
+    steered.set_level(next.warp);
+    warp.set_if_neq(steered);
+
+    fixed.set_timestep(steered.timestep(&settings));
+    on_rails.0 = steered.is_on_rails();
+}
+
+fn verify(world: &mut World) {
+    let tick = world.resource::<SimulationTick>().0;
+    let playback = world.resource::<Playback>();
+    let Some(&expected) = playback.replay.checksums.get(&tick) else {
+        return;
+    };
+    if playback.desync.is_some() {
+        return;
+    }
+
+    let actual = Snapshot::capture(world).checksum();
+    if actual != expected {
+        error!("replay desynced at tick {tick}");
+        world.resource_mut::<Playback>().desync = Some(Desync {
+            tick,
+            expected,
+            actual,
+        });
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum Mode {
+    #[default]
+    Off,
+    /// keeps a [`Recording`], saving it to the path if there is one when the app exits
+    Record(Option<PathBuf>),
+    /// plays back a [`Replay`], starting over from its scenario and settings
+    Play(Box<Replay>),
+}
+
+/// Records matches to play back later, or plays them back.
+///
+/// Needs adding after [`game::Plugin`](crate::game::Plugin),
+/// [`scenario::Plugin`](crate::scenario::Plugin) and
+/// [`settings::Plugin`](crate::settings::Plugin), so that it can record what they set up, or
+/// replace it.
+#[derive(Clone, Debug)]
+pub struct Plugin {
+    pub mode: Mode,
+    /// for recording, since playback goes by whatever the replay was recorded with
+    pub check_interval: u64,
+}
+
+impl Default for Plugin {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Off,
+            check_interval: CHECK_INTERVAL,
+        }
+    }
+}
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inputs>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<TimeWarp>()
+            .init_resource::<OnRails>()
+            .init_resource::<SimulationSettings>();
+
+        match &self.mode {
+            Mode::Off => {}
+            Mode::Record(path) => {
+                let world = app.world();
+                let replay = Replay::new(
+                    world
+                        .get_resource::<Scenario>()
+                        .cloned()
+                        .unwrap_or_default(),
+                    world.resource::<SimulationSettings>().clone(),
+                    self.check_interval,
+                );
+
+                app.insert_resource(Recording {
+                    replay,
+                    path: path.clone(),
+                })
+                .add_systems(
+                    PreUpdate,
+                    record_scenario.run_if(resource_added::<Scenario>),
+                )
+                .add_systems(FixedPreUpdate, record_tick.after(InputSet))
+                .add_systems(FixedLast, record_checksum)
+                .add_systems(Last, save_recording);
+            }
+            Mode::Play(replay) => {
+                // neither the settings file nor a scenario file is followed, so the replay's own
+                // stay put
+                app.world_mut().remove_resource::<SettingsFile>();
+                app.world_mut().remove_resource::<ScenarioFile>();
+
+                app.insert_resource(replay.scenario.clone())
+                    .insert_resource(replay.settings.clone())
+                    .insert_resource(Playback {
+                        replay: (**replay).clone(),
+                        desync: None,
+                    })
+                    .add_systems(
+                        FixedPreUpdate,
+                        play_inputs.in_set(InputSet).after(apply_local_input),
+                    )
+                    .add_systems(
+                        First,
+                        steer_warp
+                            .before(crate::warp::apply)
+                            .after(crate::settings::apply),
+                    )
+                    .add_systems(FixedLast, (steer_warp, verify));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_and_refuses_other_versions() {
+        let path = std::env::temp_dir().join(format!("spacewar-replay-{}.ron", std::process::id()));
+
+        let mut replay = Replay::new(Scenario::default(), SimulationSettings::default(), 2);
+        replay.ticks = vec![
+            Tick {
+                inputs: vec![ShipInput {
+                    thrust: true,
+                    turn: -1,
+                    ..Default::default()
+                }],
+                warp: 0,
+            },
+            Tick {
+                inputs: vec![ShipInput {
+                    fire_missile: true,
+                    ..Default::default()
+                }],
+                warp: 2,
+            },
+        ];
+        replay.checksums.insert(2, 0xdead_beef);
+
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        assert_eq!(loaded.ticks, replay.ticks);
+        assert_eq!(loaded.checksums, replay.checksums);
+        assert_eq!(loaded.settings, replay.settings);
+
+        replay.version = VERSION + 1;
+        replay.save(&path).unwrap();
+        let err = Replay::load(&path).unwrap_err();
+        assert!(err.contains("version"), "{err}");
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,428 @@
+use crate::{
+    KeyBinds, KeyPair, Missile, Ship, SimulationSettings, Transform2d, Velocity,
+    fragment::Fragment,
+    input::Player,
+    kinematics::{AlignToVelocity, SimulationTick, SimulationTime},
+    mine::Mine,
+    rng::SimulationRng,
+    ship::{Energy, Health},
+    transform2d::Teleported,
+    turret::{Interceptor, PointDefense},
+    weapon::Owner,
+};
+use bevy::{
+    ecs::entity::{EntityHashMap, EntityHashSet},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, hash::Hasher};
+
+/// how many snapshots the scrub keys move through per frame with shift held
+const FAST_SCRUB: usize = 10;
+
+/// Everything about one dynamic body that the simulation depends on.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Body {
+    pub entity: Entity,
+    pub transform: Transform2d,
+    pub velocity: Velocity,
+    pub ship: Option<(Ship, Health, Energy)>,
+    pub point_defense: bool,
+    pub missile: Option<Missile>,
+    pub mine: Option<Mine>,
+    pub fragment: Option<Fragment>,
+    pub interceptor: Option<Interceptor>,
+    pub align_to_velocity: bool,
+    pub owner: Option<Owner>,
+    pub player: Option<Player>,
+}
+
+impl Body {
+    fn capture(entity: EntityRef) -> Option<Self> {
+        Some(Self {
+            entity: entity.id(),
+            transform: *entity.get::<Transform2d>()?,
+            velocity: *entity.get::<Velocity>()?,
+            ship: entity.get::<Ship>().map(|ship| {
+                (
+                    ship.clone(),
+                    entity.get::<Health>().copied().unwrap_or_default(),
+                    entity.get::<Energy>().copied().unwrap_or_default(),
+                )
+            }),
+            point_defense: entity.contains::<PointDefense>(),
+            missile: entity.get::<Missile>().cloned(),
+            mine: entity.get::<Mine>().cloned(),
+            fragment: entity.get::<Fragment>().cloned(),
+            interceptor: entity.get::<Interceptor>().cloned(),
+            align_to_velocity: entity.contains::<AlignToVelocity>(),
+            owner: entity.get::<Owner>().copied(),
+            player: entity.get::<Player>().copied(),
+        })
+    }
+
+    fn apply(&self, mut entity: EntityWorldMut) {
+        entity.insert((self.transform, self.velocity));
+
+        fn insert_or_remove<T: Bundle>(entity: &mut EntityWorldMut, value: Option<T>) {
+            match value {
+                Some(value) => {
+                    entity.insert(value);
+                }
+                None => {
+                    entity.remove::<T>();
+                }
+            }
+        }
+
+        insert_or_remove(&mut entity, self.ship.clone());
+        insert_or_remove(&mut entity, self.point_defense.then_some(PointDefense));
+        insert_or_remove(&mut entity, self.missile.clone());
+        insert_or_remove(&mut entity, self.mine.clone());
+        insert_or_remove(&mut entity, self.fragment.clone());
+        insert_or_remove(&mut entity, self.interceptor.clone());
+        insert_or_remove(
+            &mut entity,
+            self.align_to_velocity.then_some(AlignToVelocity),
+        );
+        insert_or_remove(&mut entity, self.owner);
+        insert_or_remove(&mut entity, self.player);
+    }
+
+    /// Everything but the entities, which needn't match between runs of the same simulation.
+    fn hash(&self, state: &mut impl Hasher) {
+        fn floats(state: &mut impl Hasher, values: &[f32]) {
+            for value in values {
+                state.write_u32(value.to_bits());
+            }
+        }
+
+        let Transform2d {
+            translation,
+            rotation,
+            ..
+        } = self.transform;
+        floats(
+            state,
+            &[translation.x, translation.y, rotation.cos, rotation.sin],
+        );
+        floats(state, &self.velocity.0.to_array());
+
+        if let Some((ship, health, energy)) = &self.ship {
+            floats(state, &[ship.rotational_velocity, health.0, energy.0]);
+            state.write_u8(ship.sas.map_or(0, |sas| sas as u8 + 1));
+        }
+        if let Some(missile) = &self.missile {
+            floats(state, &[missile.age, missile.fuse_radius]);
+        }
+        if let Some(mine) = &self.mine {
+            floats(state, &[mine.age]);
+        }
+        if let Some(fragment) = &self.fragment {
+            floats(state, &[fragment.age, fragment.damage, fragment.lifetime]);
+        }
+        if let Some(interceptor) = &self.interceptor {
+            floats(state, &[interceptor.age]);
+        }
+        if let Some(player) = self.player {
+            state.write_u64(player.0 as u64);
+        }
+    }
+
+    fn remap(&mut self, map: &EntityHashMap<Entity>) {
+        let remap = |entity: &mut Entity| {
+            if let Some(mapped) = map.get(entity) {
+                *entity = *mapped;
+            }
+        };
+
+        remap(&mut self.entity);
+        if let Some(owner) = &mut self.owner {
+            remap(&mut owner.0);
+        }
+    }
+}
+
+/// Every dynamic body at the end of one fixed tick.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub bodies: Vec<Body>,
+    /// so that whatever happens by chance after this happens the same way again
+    pub rng: Option<SimulationRng>,
+    /// [`SimulationTime`], which anything orbiting on rails is placed by
+    pub time: f64,
+}
+
+/// Present after a [`Snapshot`] has been restored, until anything placed by [`SimulationTime`]
+/// rather than restored along with the bodies has caught up.
+#[derive(Clone, Copy, Debug, Default, Resource)]
+pub struct Restored;
+
+impl Snapshot {
+    /// Everything moving under gravity, which is everything with a [`Velocity`].
+    pub fn capture(world: &mut World) -> Self {
+        let bodies = world
+            .query_filtered::<EntityRef, With<Velocity>>()
+            .iter(world)
+            .filter_map(Body::capture)
+            .collect();
+
+        Self {
+            tick: world.resource::<SimulationTick>().0,
+            bodies,
+            rng: world.get_resource::<SimulationRng>().cloned(),
+            time: world
+                .get_resource::<SimulationTime>()
+                .map_or(0.0, |time| time.0),
+        }
+    }
+
+    /// Puts the world back the way it was, respawning bodies that have since been despawned and
+    /// despawning any that didn't exist yet.
+    ///
+    /// Respawned bodies get new entities. The returned map from old to new entities is already
+    /// applied to references within the snapshot, but anything else holding on to old entities
+    /// needs to be remapped with it.
+    pub fn restore(&self, world: &mut World) -> EntityHashMap<Entity> {
+        let wanted: EntityHashSet = self.bodies.iter().map(|body| body.entity).collect();
+        let current: Vec<Entity> = world
+            .query_filtered::<Entity, With<Velocity>>()
+            .iter(world)
+            .collect();
+
+        for entity in current.iter().filter(|entity| !wanted.contains(*entity)) {
+            world.despawn(*entity);
+        }
+
+        let mut map = EntityHashMap::default();
+        for body in &self.bodies {
+            if !current.contains(&body.entity) {
+                map.insert(body.entity, world.spawn_empty().id());
+            }
+        }
+
+        for body in &self.bodies {
+            let mut body = body.clone();
+            body.remap(&map);
+            body.apply(world.entity_mut(body.entity));
+        }
+
+        world.resource_mut::<SimulationTick>().0 = self.tick;
+        if let Some(rng) = &self.rng {
+            world.insert_resource(rng.clone());
+        }
+        world.insert_resource(SimulationTime(self.time));
+        world.insert_resource(Restored);
+        map
+    }
+
+    /// Marks every body as having jumped straight to where it is, so that it isn't drawn sliding
+    /// over from wherever it was before. Not for rolling back and resimulating, which ends up
+    /// with proper previous transforms of its own.
+    pub fn teleport(&self, world: &mut World) {
+        for body in &self.bodies {
+            world.entity_mut(body.entity).insert(Teleported);
+        }
+    }
+
+    pub fn remap(&mut self, map: &EntityHashMap<Entity>) {
+        for body in &mut self.bodies {
+            body.remap(map);
+        }
+    }
+
+    /// A hash of the state of every body, stable across runs and platforms, for checking that
+    /// two simulations haven't drifted apart.
+    pub fn checksum(&self) -> u64 {
+        // the time is left out, since any difference in it shows up in the bodies soon enough
+        let mut state = Fnv1a::default();
+        state.write_u64(self.tick);
+        for body in &self.bodies {
+            body.hash(&mut state);
+        }
+        state.finish()
+    }
+
+    /// Roughly how much memory this takes up.
+    pub fn size(&self) -> usize {
+        size_of::<Self>() + self.bodies.capacity() * size_of::<Body>()
+    }
+}
+
+/// The standard library's hasher is free to change between releases, so checksums use this
+/// instead.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // the defaults go by native byte order
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+/// The most recent snapshots, oldest first, kept under
+/// [`SimulationSettings::rewind_memory`].
+#[derive(Debug, Default, Resource)]
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    size: usize,
+    /// the snapshot currently being looked at while scrubbing, if not the latest
+    cursor: Option<usize>,
+    /// the cursor moved, so the world needs catching up
+    dirty: bool,
+}
+
+impl History {
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Memory taken up by every snapshot, going by [`Snapshot::size`].
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn snapshots(&self) -> impl DoubleEndedIterator<Item = &Snapshot> {
+        self.snapshots.iter()
+    }
+
+    pub fn get(&self, tick: u64) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.tick == tick)
+    }
+
+    /// Adds `snapshot` as the latest, dropping anything after the cursor and then the oldest
+    /// snapshots until it all fits in `max_size`.
+    pub fn push(&mut self, snapshot: Snapshot, max_size: usize) {
+        if let Some(cursor) = self.cursor.take() {
+            for dropped in self.snapshots.drain(cursor + 1..) {
+                self.size -= dropped.size();
+            }
+        }
+
+        self.size += snapshot.size();
+        self.snapshots.push_back(snapshot);
+
+        while self.size > max_size
+            && let Some(dropped) = self.snapshots.pop_front()
+        {
+            self.size -= dropped.size();
+        }
+    }
+
+    /// Moves the cursor `steps` snapshots back, or forward if negative.
+    pub fn scrub(&mut self, steps: isize) {
+        let Some(latest) = self.snapshots.len().checked_sub(1) else {
+            return;
+        };
+
+        let cursor = self.cursor.unwrap_or(latest);
+        let target = cursor.saturating_add_signed(-steps).min(latest);
+        if target != cursor {
+            self.cursor = (target != latest).then_some(target);
+            self.dirty = true;
+        }
+    }
+
+    /// Throws away everything after `tick` and restores the world to how it was then.
+    pub fn rewind_to(&mut self, tick: u64, world: &mut World) -> bool {
+        let Some(index) = self.snapshots.iter().position(|s| s.tick == tick) else {
+            return false;
+        };
+
+        for dropped in self.snapshots.drain(index + 1..) {
+            self.size -= dropped.size();
+        }
+        self.cursor = None;
+        self.restore(index, world);
+
+        true
+    }
+
+    fn restore(&mut self, index: usize, world: &mut World) {
+        let map = self.snapshots[index].restore(world);
+        for snapshot in self.snapshots.iter_mut() {
+            snapshot.remap(&map);
+        }
+        self.dirty = false;
+    }
+}
+
+fn record(world: &mut World) {
+    let snapshot = Snapshot::capture(world);
+    let max_size = world.resource::<SimulationSettings>().rewind_memory;
+    world.resource_mut::<History>().push(snapshot, max_size);
+}
+
+fn scrub(
+    mut history: ResMut<History>,
+    mut time: ResMut<Time<Virtual>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<KeyBinds>,
+) {
+    let steps = if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        FAST_SCRUB as isize
+    } else {
+        1
+    };
+
+    if keys.any_pressed(keybinds.rewind().map(KeyPair::left)) {
+        history.scrub(steps);
+    } else if keys.any_pressed(keybinds.rewind().map(KeyPair::right)) {
+        history.scrub(-steps);
+    } else {
+        return;
+    }
+
+    // play resumes from wherever scrubbing stopped once unpaused
+    time.pause();
+}
+
+fn restore_cursor(world: &mut World) {
+    world.resource_scope(|world, mut history: Mut<History>| {
+        if !history.dirty {
+            return;
+        }
+
+        let index = history.cursor.unwrap_or(history.len() - 1);
+        history.restore(index, world);
+        history.snapshots[index].teleport(world);
+    });
+}
+
+/// Keeps a history of every dynamic body, to scrub back through and play on from.
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .init_resource::<SimulationSettings>()
+            .init_resource::<SimulationTick>()
+            .add_systems(FixedLast, record)
+            .add_systems(Update, (scrub, restore_cursor).chain());
+    }
+}
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// The simulation's only source of randomness, so that a match plays out exactly the same way
+/// every time it's run from the same seed.
+///
+/// ChaCha rather than `StdRng` since its output is guaranteed not to change between versions,
+/// which replays recorded with an older build depend on.
+#[derive(Clone, Debug, PartialEq, Eq, Resource, Serialize, Deserialize)]
+pub struct SimulationRng(pub ChaCha8Rng);
+
+impl SimulationRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl Default for SimulationRng {
+    fn default() -> Self {
+        Self::seeded(0)
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{
+    GravityField, KeyBinds, Scenario, SimulationSettings, Star, TimeWarp, Transform2d, game,
+    net::Session,
+    replay::{Playback, Recording},
+    rewind::{History, Snapshot},
+    star::{self, Orbiting},
+    transform2d::Teleported,
+};
+use bevy::{ecs::entity::EntityHashMap, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Saves in any other version of the format are refused, unless they can be migrated.
+pub const VERSION: u32 = 1;
+
+/// A star, under the entity it had when saved.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedStar {
+    pub entity: Entity,
+    pub name: Option<String>,
+    pub star: Star,
+    pub transform: Transform2d,
+    pub orbiting: Option<Orbiting>,
+}
+
+/// A match as it stood at the end of a fixed tick, to carry on from exactly there.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    /// for where ships respawn, and the rules
+    pub scenario: Scenario,
+    pub stars: Vec<SavedStar>,
+    /// kept as is rather than rebuilt from the stars, since the order the masses were added in
+    /// decides how their pulls add up
+    pub gravity: GravityField,
+    /// ships, projectiles, and the tick, time and random numbers they've got to
+    pub bodies: Snapshot,
+    /// [`TimeWarp`] level
+    pub warp: usize,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// saved in a version of the format there's no migrating from
+    Version {
+        found: u32,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Parse(err) => write!(f, "couldn't parse save: {err}"),
+            Self::Serialize(err) => write!(f, "couldn't serialize save: {err}"),
+            Self::Version { found } if *found > VERSION => write!(
+                f,
+                "saved by a newer build, as version {found}, but only up to version {VERSION} can \
+                 be loaded"
+            ),
+            Self::Version { found } => write!(
+                f,
+                "saved as version {found}, which can't be migrated to version {VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Serialize(err) => Some(err),
+            Self::Version { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl SaveGame {
+    pub fn capture(world: &mut World) -> Self {
+        let stars = world
+            .query::<(
+                Entity,
+                &Star,
+                &Transform2d,
+                Option<&Name>,
+                Option<&Orbiting>,
+            )>()
+            .iter(world)
+            .map(|(entity, star, transform, name, orbiting)| SavedStar {
+                entity,
+                name: name.map(|name| name.as_str().to_owned()),
+                star: *star,
+                transform: *transform,
+                orbiting: orbiting.copied(),
+            })
+            .collect();
+
+        Self {
+            version: VERSION,
+            scenario: world
+                .get_resource::<Scenario>()
+                .cloned()
+                .unwrap_or_default(),
+            stars,
+            gravity: world.resource::<GravityField>().clone(),
+            bodies: Snapshot::capture(world),
+            warp: world.get_resource::<TimeWarp>().map_or(0, TimeWarp::level),
+        }
+    }
+
+    /// Replaces the whole match with the saved one. History from before it is forgotten, since
+    /// there's no rewinding into a different match.
+    pub fn restore(&self, world: &mut World) {
+        let stars: Vec<Entity> = world
+            .query_filtered::<Entity, With<Star>>()
+            .iter(world)
+            .collect();
+        for entity in stars {
+            world.despawn(entity);
+        }
+
+        let mut map = EntityHashMap::default();
+        for saved in &self.stars {
+            let mut entity = world.spawn((
+                star::Bundle {
+                    transform: saved.transform,
+                    star: saved.star,
+                },
+                Teleported,
+            ));
+            if let Some(name) = &saved.name {
+                entity.insert(Name::new(name.clone()));
+            }
+            map.insert(saved.entity, entity.id());
+        }
+        for saved in &self.stars {
+            if let Some(mut orbiting) = saved.orbiting {
+                orbiting.around = map
+                    .get(&orbiting.around)
+                    .copied()
+                    .unwrap_or(orbiting.around);
+                world.entity_mut(map[&saved.entity]).insert(orbiting);
+            }
+        }
+
+        let mut gravity = self.gravity.clone();
+        gravity.remap(&map);
+        world.insert_resource(gravity);
+
+        let mut bodies = self.bodies.clone();
+        let map = bodies.restore(world);
+        bodies.remap(&map);
+        bodies.teleport(world);
+
+        if let Some(mut warp) = world.get_resource_mut::<TimeWarp>() {
+            warp.set_level(self.warp);
+        }
+        // changed rather than inserted, so that the match isn't started all over again
+        if let Some(mut scenario) = world.get_resource_mut::<Scenario>() {
+            scenario.clone_from(&self.scenario);
+        }
+
+        if world.contains_resource::<History>() {
+            let max_size = world.resource::<SimulationSettings>().rewind_memory;
+            let mut history = History::default();
+            history.push(bodies, max_size);
+            world.insert_resource(history);
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SaveError> {
+        #[derive(Deserialize)]
+        struct Header {
+            version: u32,
+        }
+
+        let text = std::fs::read_to_string(path)?;
+        let header: Header = ron::from_str(&text)?;
+        Self::migrate(header.version, &text)
+    }
+
+    /// Reads a save made in `version` of the format, bringing it up to date if it's older.
+    fn migrate(version: u32, text: &str) -> Result<Self, SaveError> {
+        match version {
+            VERSION => Ok(ron::from_str(text)?),
+            // older versions get an arm here, parsing them as they were and filling in the rest
+            found => Err(SaveError::Version { found }),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(SaveError::Serialize)?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Where quicksaves go.
+#[derive(Clone, Debug, Resource)]
+struct SaveFile(PathBuf);
+
+/// A save to carry on from, before the coming frame's ticks.
+#[derive(Debug, Resource)]
+struct PendingLoad(Box<SaveGame>);
+
+fn quicksave(world: &mut World) {
+    let keybinds = world.resource::<KeyBinds>();
+    if !world
+        .resource::<ButtonInput<KeyCode>>()
+        .any_just_pressed(keybinds.quicksave())
+    {
+        return;
+    }
+
+    let path = world.resource::<SaveFile>().0.clone();
+    match SaveGame::capture(world).save(&path) {
+        Ok(()) => info!("saved to {}", path.display()),
+        Err(err) => error!("couldn't save to {}: {err}", path.display()),
+    }
+}
+
+fn quickload(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<KeyBinds>,
+    file: Res<SaveFile>,
+    busy: Option<Res<Session>>,
+    recording: Option<Res<Recording>>,
+    playback: Option<Res<Playback>>,
+) {
+    if !keys.any_just_pressed(keybinds.quickload()) {
+        return;
+    }
+
+    // none of these could follow the match jumping somewhere else
+    if busy.is_some() || recording.is_some() || playback.is_some() {
+        warn!("can't load while recording, playing back or playing over the network");
+        return;
+    }
+
+    match SaveGame::load(&file.0) {
+        Ok(save) => commands.insert_resource(PendingLoad(Box::new(save))),
+        Err(err) => error!("couldn't load {}: {err}", file.0.display()),
+    }
+}
+
+fn apply_pending(world: &mut World) {
+    if let Some(PendingLoad(save)) = world.remove_resource::<PendingLoad>() {
+        save.restore(world);
+        info!("carrying on from tick {}", save.bodies.tick);
+    }
+}
+
+/// Saves the match at the press of a key and loads it back at another, and can start a match
+/// from a save.
+#[derive(Clone, Debug)]
+pub struct Plugin {
+    /// where quicksaves go, and are loaded from
+    pub path: PathBuf,
+    /// to carry on from, in place of starting the scenario afresh
+    pub load: Option<Box<SaveGame>>,
+}
+
+impl Default for Plugin {
+    fn default() -> Self {
+        Self {
+            path: "quicksave.ron".into(),
+            load: None,
+        }
+    }
+}
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyBinds>()
+            .insert_resource(SaveFile(self.path.clone()))
+            .add_systems(
+                PreUpdate,
+                apply_pending
+                    .run_if(resource_exists::<PendingLoad>)
+                    .after(game::start_match),
+            )
+            .add_systems(Update, (quicksave, quickload));
+
+        if let Some(save) = &self.load {
+            app.insert_resource(PendingLoad(save.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_versions_it_cannot_migrate() {
+        let text = ron::ser::to_string(&SaveGame {
+            version: VERSION + 1,
+            scenario: Scenario::default(),
+            stars: Vec::new(),
+            gravity: GravityField::default(),
+            bodies: Snapshot::default(),
+            warp: 0,
+        })
+        .unwrap();
+
+        let err = SaveGame::migrate(VERSION + 1, &text).unwrap_err();
+        assert!(matches!(err, SaveError::Version { found } if found == VERSION + 1));
+        assert!(err.to_string().contains("newer build"), "{err}");
+
+        let err = SaveGame::migrate(0, &text).unwrap_err();
+        assert!(err.to_string().contains("can't be migrated"), "{err}");
+    }
+}
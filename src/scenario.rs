@@ -0,0 +1,424 @@
+use crate::{input::Player, kepler::Orbit};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, io::Reader},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::Path};
+
+/// Everything set up at the start of a match: what's in the sky, where ships start, and how it's
+/// played.
+///
+/// Loaded from `.scenario.ron` files through the asset server by [`Plugin`]. Fields left out of a
+/// file keep their defaults.
+#[derive(Clone, Debug, PartialEq, Resource, Asset, TypePath, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    /// stars and anything else with gravity, each listed after whatever it orbits
+    pub bodies: Vec<Body>,
+    /// where ships start, handed out to players in turn
+    pub spawns: Vec<Spawn>,
+    pub rules: Rules,
+    pub camera: CameraStart,
+    /// for [`SimulationRng`](crate::rng::SimulationRng), so that matches can be played out again
+    pub seed: u64,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        let spawn = Spawn {
+            position: Vec2::new(-1000.0, 500.0),
+            velocity: Vec2::X * 50.0,
+        };
+
+        Self {
+            bodies: vec![Body {
+                name: "star".into(),
+                mass: 1.6e16,
+                radius: 10.0,
+                placement: Placement::Fixed(Vec2::ZERO),
+            }],
+            // the second player starts on the opposite side, heading the opposite way
+            spawns: vec![
+                spawn,
+                Spawn {
+                    position: -spawn.position,
+                    velocity: -spawn.velocity,
+                },
+            ],
+            rules: Rules::default(),
+            camera: CameraStart::default(),
+            seed: 0,
+        }
+    }
+}
+
+/// A star, or anything else with gravity.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Body {
+    /// for other bodies to orbit it by
+    #[serde(default)]
+    pub name: String,
+    pub mass: f32,
+    pub radius: f32,
+    #[serde(default)]
+    pub placement: Placement,
+}
+
+/// Where a [`Body`] is, and how it moves.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Placement {
+    /// staying put
+    Fixed(Vec2),
+    /// orbiting the body named `around`, starting at `position` relative to it, at `velocity`
+    State {
+        around: String,
+        position: Vec2,
+        velocity: Vec2,
+    },
+    /// orbiting the body named `around`, along an orbit given by its elements
+    Elements {
+        around: String,
+        /// negative for hyperbolic orbits
+        semi_major_axis: f64,
+        eccentricity: f64,
+        /// from the x axis, in degrees
+        argument_of_periapsis: f64,
+        /// where along the orbit it starts, in degrees
+        true_anomaly: f64,
+        /// whether it goes anticlockwise
+        prograde: bool,
+    },
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self::Fixed(Vec2::ZERO)
+    }
+}
+
+/// Where a ship starts, and how fast.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Spawn {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// whether ships can be reset, or brought back once destroyed
+    pub respawn: bool,
+    /// whether ships come with turrets that shoot down missiles
+    pub point_defense: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            respawn: true,
+            point_defense: true,
+        }
+    }
+}
+
+/// Where the camera starts out looking.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraStart {
+    pub center: Vec2,
+    /// world units per pixel
+    pub scale: f32,
+}
+
+impl Default for CameraStart {
+    fn default() -> Self {
+        Self {
+            center: Vec2::ZERO,
+            scale: 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    /// a body orbits one that isn't listed before it
+    UnknownBody {
+        body: String,
+        around: String,
+    },
+    /// a body's orbit can't be followed
+    BadOrbit {
+        body: String,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read scenario: {err}"),
+            Self::Parse(err) => write!(f, "couldn't parse scenario: {err}"),
+            Self::UnknownBody { body, around } => write!(
+                f,
+                "{body:?} orbits {around:?}, which isn't listed before it"
+            ),
+            Self::BadOrbit { body, reason } => write!(f, "{body:?} can't orbit: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ScenarioError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for ScenarioError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl Scenario {
+    /// Parses and checks a scenario, the same as loading it through the asset server would.
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, ScenarioError> {
+        let scenario: Self = ron::de::from_bytes(bytes)?;
+
+        // any gravitational constant will do, since it only scales how fast things go around
+        for index in 0..scenario.bodies.len() {
+            scenario.orbit(index, 1.0)?;
+        }
+
+        Ok(scenario)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::from_ron(&std::fs::read(path)?)
+    }
+
+    /// Where `player`'s ship starts.
+    pub fn spawn(&self, player: Player) -> Spawn {
+        if self.spawns.is_empty() {
+            return Spawn::default();
+        }
+        self.spawns[player.0 % self.spawns.len()]
+    }
+
+    /// The orbit the body at `index` follows, and the index of the body it's around, if it
+    /// moves at all.
+    pub fn orbit(
+        &self,
+        index: usize,
+        gravitational_constant: f32,
+    ) -> Result<Option<(usize, Orbit)>, ScenarioError> {
+        let body = &self.bodies[index];
+        let around = match &body.placement {
+            Placement::Fixed(_) => return Ok(None),
+            Placement::State { around, .. } | Placement::Elements { around, .. } => around,
+        };
+
+        let parent = self.bodies[..index]
+            .iter()
+            .position(|other| other.name == *around)
+            .ok_or_else(|| ScenarioError::UnknownBody {
+                body: body.name.clone(),
+                around: around.clone(),
+            })?;
+        let mu = gravitational_constant * self.bodies[parent].mass;
+        let bad_orbit = |reason| ScenarioError::BadOrbit {
+            body: body.name.clone(),
+            reason,
+        };
+
+        if mu <= 0.0 {
+            return Err(bad_orbit("what it orbits has no mass"));
+        }
+
+        let orbit = match body.placement {
+            Placement::Fixed(_) => unreachable!(),
+            Placement::State {
+                position, velocity, ..
+            } => Orbit::from_state(position, velocity, mu)
+                .ok_or_else(|| bad_orbit("it's parabolic, or falling straight in"))?,
+            Placement::Elements {
+                semi_major_axis,
+                eccentricity,
+                argument_of_periapsis,
+                true_anomaly,
+                prograde,
+                ..
+            } => {
+                if eccentricity < 0.0 || (eccentricity - 1.0).abs() < 1e-6 {
+                    return Err(bad_orbit("eccentricity has to be positive, and not 1"));
+                }
+                if (eccentricity > 1.0) != (semi_major_axis < 0.0) {
+                    return Err(bad_orbit(
+                        "semi-major axis has to be negative for hyperbolic orbits, and only then",
+                    ));
+                }
+
+                Orbit {
+                    mu: mu as f64,
+                    semi_major_axis,
+                    eccentricity,
+                    argument_of_periapsis: argument_of_periapsis.to_radians(),
+                    true_anomaly: true_anomaly.to_radians(),
+                    prograde,
+                }
+            }
+        };
+
+        Ok(Some((parent, orbit)))
+    }
+}
+
+#[derive(Default, TypePath)]
+pub struct ScenarioLoader;
+
+impl AssetLoader for ScenarioLoader {
+    type Asset = Scenario;
+    type Settings = ();
+    type Error = ScenarioError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Scenario, ScenarioError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Scenario::from_ron(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["scenario.ron"]
+    }
+}
+
+/// The scenario file being loaded, until it has been.
+#[derive(Debug, Resource)]
+pub(crate) struct ScenarioFile {
+    path: String,
+    handle: Option<Handle<Scenario>>,
+    seed: Option<u64>,
+}
+
+/// Holds the simulation still while the file loads, so that nothing happens before there's
+/// anything to happen to.
+fn start_loading(
+    mut file: ResMut<ScenarioFile>,
+    asset_server: Res<AssetServer>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    file.handle = Some(asset_server.load(&file.path));
+    time.pause();
+}
+
+pub(crate) fn finish_loading(
+    mut commands: Commands,
+    file: Res<ScenarioFile>,
+    scenarios: Res<Assets<Scenario>>,
+    asset_server: Res<AssetServer>,
+    mut time: ResMut<Time<Virtual>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(handle) = &file.handle else {
+        return;
+    };
+
+    if let Some(scenario) = scenarios.get(handle) {
+        info!("loaded {}", file.path);
+        let mut scenario = scenario.clone();
+        scenario.seed = file.seed.unwrap_or(scenario.seed);
+        commands.insert_resource(scenario);
+        commands.remove_resource::<ScenarioFile>();
+        time.unpause();
+    } else if let LoadState::Failed(err) = asset_server.load_state(handle) {
+        error!("couldn't load {}: {err}", file.path);
+        exit.write(AppExit::error());
+    }
+}
+
+/// Loads `.scenario.ron` assets, and starts the match from one if given a path to it.
+#[derive(Clone, Debug, Default)]
+pub struct Plugin {
+    /// asset path of the scenario to play, in place of whatever [`Scenario`] was set up already
+    pub path: Option<String>,
+    /// in place of the scenario's own
+    pub seed: Option<u64>,
+}
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Scenario>()
+            .init_asset_loader::<ScenarioLoader>();
+
+        if let Some(seed) = self.seed
+            && let Some(mut scenario) = app.world_mut().get_resource_mut::<Scenario>()
+        {
+            scenario.seed = seed;
+        }
+
+        if let Some(path) = &self.path {
+            app.world_mut().remove_resource::<Scenario>();
+            app.insert_resource(ScenarioFile {
+                path: path.clone(),
+                handle: None,
+                seed: self.seed,
+            })
+            .add_systems(
+                Startup,
+                start_loading.run_if(resource_exists::<ScenarioFile>),
+            )
+            .add_systems(
+                PreUpdate,
+                finish_loading.run_if(resource_exists::<ScenarioFile>),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_scenarios_load() {
+        let default = Scenario::load("assets/scenarios/default.scenario.ron").unwrap();
+        assert_eq!(default, Scenario::default());
+
+        let binary = Scenario::load("assets/scenarios/binary.scenario.ron").unwrap();
+        assert!(binary.orbit(1, 1.0).unwrap().is_some());
+    }
+
+    #[test]
+    fn refuses_orbits_around_unknown_bodies() {
+        let text = r#"(
+            bodies: [
+                (name: "moon", mass: 1.0, radius: 1.0, placement: State(
+                    around: "planet",
+                    position: (10.0, 0.0),
+                    velocity: (0.0, 1.0),
+                )),
+                (name: "planet", mass: 100.0, radius: 5.0),
+            ],
+        )"#;
+
+        let err = Scenario::from_ron(text.as_bytes()).unwrap_err();
+        assert!(matches!(err, ScenarioError::UnknownBody { .. }), "{err}");
+    }
+}
@@ -0,0 +1,406 @@
+use crate::{GravityField, gravity::GRAVITATIONAL_CONSTANT, weapon::Weapon};
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader, io::file::FileAssetReader},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, path::Path, time::Duration};
+
+/// Everything about how the simulation runs that's worth tuning without recompiling.
+///
+/// Read from a `.ron` asset by [`Plugin`], which also picks up any changes made to it while the
+/// game is running. Fields left out of the file keep their defaults.
+#[derive(Clone, Debug, PartialEq, Resource, Asset, TypePath, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationSettings {
+    pub gravitational_constant: f32,
+    /// fixed ticks per second
+    pub tick_rate: f64,
+    /// integration steps per fixed tick
+    pub substeps: u32,
+    /// speed gained per second of holding accelerate
+    pub thrust: f32,
+    /// integration steps spent predicting each ship's trajectory
+    pub trajectory_steps: usize,
+    pub trail_length: usize,
+    /// seconds until projectiles despawn, by [short type path](TypePath::short_type_path), for
+    /// any that shouldn't last as long as they do otherwise
+    pub lifetimes: BTreeMap<String, f32>,
+    /// bytes of history kept around for rewinding
+    pub rewind_memory: usize,
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        Self {
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+            tick_rate: 64.0,
+            substeps: 1,
+            thrust: 32.0,
+            trajectory_steps: 5000,
+            trail_length: 20,
+            lifetimes: BTreeMap::new(),
+            rewind_memory: 32 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read settings: {err}"),
+            Self::Parse(err) => write!(f, "couldn't parse settings: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for SettingsError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for SettingsError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl SimulationSettings {
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, SettingsError> {
+        Ok(ron::de::from_bytes(bytes)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SettingsError> {
+        Self::from_ron(&std::fs::read(path)?)
+    }
+
+    /// Seconds until `projectile` despawns.
+    pub fn max_age<W: Weapon>(&self, projectile: &W) -> f32 {
+        self.lifetimes
+            .get(W::short_type_path())
+            .copied()
+            .unwrap_or_else(|| projectile.max_age())
+    }
+
+    pub fn timestep(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.tick_rate)
+    }
+}
+
+/// Chosen by asset type rather than extension, so the file can be called anything ending in
+/// `.ron`.
+#[derive(Default, TypePath)]
+pub struct SettingsLoader;
+
+impl AssetLoader for SettingsLoader {
+    type Asset = SimulationSettings;
+    type Settings = ();
+    type Error = SettingsError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<SimulationSettings, SettingsError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        SimulationSettings::from_ron(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["settings.ron"]
+    }
+}
+
+/// The settings asset being followed for changes.
+#[derive(Debug, Resource)]
+pub(crate) struct SettingsFile {
+    path: String,
+    handle: Option<Handle<SimulationSettings>>,
+    tick_rate: Option<f64>,
+}
+
+fn start_watching(mut file: ResMut<SettingsFile>, asset_server: Res<AssetServer>) {
+    file.handle = Some(asset_server.load(&file.path));
+}
+
+fn reload(
+    mut events: EventReader<AssetEvent<SimulationSettings>>,
+    file: Res<SettingsFile>,
+    assets: Res<Assets<SimulationSettings>>,
+    mut settings: ResMut<SimulationSettings>,
+) {
+    let Some(handle) = &file.handle else {
+        return;
+    };
+
+    // a file that no longer parses never gets this far, so the previous settings stay
+    for event in events.read() {
+        if event.is_modified(handle)
+            && let Some(loaded) = assets.get(handle)
+        {
+            info!("reloaded {}", file.path);
+            let mut loaded = loaded.clone();
+            loaded.tick_rate = file.tick_rate.unwrap_or(loaded.tick_rate);
+            settings.set_if_neq(loaded);
+        }
+    }
+}
+
+pub(crate) fn apply(
+    settings: Res<SimulationSettings>,
+    mut gravity: ResMut<GravityField>,
+    mut fixed: ResMut<Time<Fixed>>,
+    strategy: Option<ResMut<TimeUpdateStrategy>>,
+) {
+    gravity.set_gravitational_constant(settings.gravitational_constant);
+    fixed.set_timestep(settings.timestep());
+
+    // anything stepping time by hand expects one tick per update
+    if let Some(mut strategy) = strategy
+        && let TimeUpdateStrategy::ManualDuration(duration) = strategy.as_mut()
+    {
+        *duration = settings.timestep();
+    }
+}
+
+/// Loads [`SimulationSettings`] from the asset at `path` if there is one, and keeps them in sync
+/// with it if the asset server is watching for changes.
+#[derive(Clone, Debug)]
+pub struct Plugin {
+    /// asset path, like `settings.ron`
+    pub path: Option<String>,
+    /// used if there's no file, or it can't be loaded
+    pub settings: SimulationSettings,
+    /// in place of whatever the file or `settings` say, even after the file changes
+    pub tick_rate: Option<f64>,
+}
+
+impl Default for Plugin {
+    fn default() -> Self {
+        Self {
+            path: Some("settings.ron".into()),
+            settings: SimulationSettings::default(),
+            tick_rate: None,
+        }
+    }
+}
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let mut settings = self.settings.clone();
+
+        if let Some(path) = &self.path
+            && let Some(assets) = app.get_added_plugins::<AssetPlugin>().first()
+        {
+            // read straight away as well, rather than only once the asset server gets round to
+            // it, so that the very first tick runs at the right rate and recordings start out
+            // with the right settings
+            let file = FileAssetReader::get_base_path()
+                .join(&assets.file_path)
+                .join(path);
+            if file.exists() {
+                match SimulationSettings::load(&file) {
+                    Ok(loaded) => settings = loaded,
+                    Err(err) => error!("{}: {err}, using the default settings", file.display()),
+                }
+            }
+
+            app.init_asset::<SimulationSettings>()
+                .init_asset_loader::<SettingsLoader>()
+                .insert_resource(SettingsFile {
+                    path: path.clone(),
+                    handle: None,
+                    tick_rate: self.tick_rate,
+                })
+                .add_systems(
+                    Startup,
+                    start_watching.run_if(resource_exists::<SettingsFile>),
+                )
+                .add_systems(Update, reload.run_if(resource_exists::<SettingsFile>));
+        }
+
+        settings.tick_rate = self.tick_rate.unwrap_or(settings.tick_rate);
+
+        if let Some(mut fixed) = app.world_mut().get_resource_mut::<Time<Fixed>>() {
+            fixed.set_timestep(settings.timestep());
+        }
+
+        app.insert_resource(settings)
+            .init_resource::<GravityField>()
+            .add_systems(
+                First,
+                apply
+                    .run_if(resource_changed::<SimulationSettings>)
+                    .before(bevy::time::TimeSystem),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+
+    #[test]
+    fn bundled_settings_are_the_defaults() {
+        assert_eq!(
+            SimulationSettings::load("assets/settings.ron").unwrap(),
+            SimulationSettings::default()
+        );
+    }
+
+    #[test]
+    fn lifetimes_override_weapons_own() {
+        use crate::{Missile, mine::Mine};
+
+        let mut settings = SimulationSettings::default();
+        assert_eq!(settings.max_age(&Missile::default()), Missile::MAX_AGE);
+
+        settings.lifetimes.insert("Missile".into(), 5.0);
+        assert_eq!(settings.max_age(&Missile::default()), 5.0);
+        assert_eq!(settings.max_age(&Mine::default()), Mine::MAX_AGE);
+    }
+
+    #[test]
+    fn reloads_when_the_asset_changes() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            Plugin {
+                path: Some("missing.settings.ron".into()),
+                settings: SimulationSettings {
+                    substeps: 4,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ));
+        app.update();
+
+        let settings = |app: &App| app.world().resource::<SimulationSettings>().clone();
+        assert_eq!(settings(&app).substeps, 4);
+
+        // as if the file watcher had picked up an edit
+        let id = app
+            .world()
+            .resource::<SettingsFile>()
+            .handle
+            .as_ref()
+            .unwrap()
+            .id();
+        app.world_mut()
+            .resource_mut::<Assets<SimulationSettings>>()
+            .insert(
+                id,
+                SimulationSettings {
+                    tick_rate: 32.0,
+                    ..Default::default()
+                },
+            );
+        app.world_mut().send_event(AssetEvent::Modified { id });
+        app.update();
+        assert_eq!(settings(&app).tick_rate, 32.0);
+        assert_eq!(settings(&app).substeps, 1);
+
+        // which only takes effect at the start of the next frame
+        app.update();
+        assert_eq!(
+            app.world().resource::<Time<Fixed>>().timestep(),
+            Duration::from_secs_f64(1.0 / 32.0)
+        );
+    }
+
+    #[test]
+    #[ignore = "waits on the OS file watcher, however long that takes"]
+    fn reloads_when_the_file_changes() {
+        let dir = std::env::temp_dir().join(format!("spacewar-settings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.ron");
+        std::fs::write(&path, "(substeps: 4)").unwrap();
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: dir.to_string_lossy().into_owned(),
+                watch_for_changes_override: Some(true),
+                ..Default::default()
+            },
+            Plugin::default(),
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            10,
+        )));
+
+        let settings = |app: &App| app.world().resource::<SimulationSettings>().clone();
+        // until the file watcher notices, which takes however long it takes
+        let update_until = |app: &mut App, done: &dyn Fn(&App) -> bool| {
+            for _ in 0..500 {
+                app.update();
+                if done(app) {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        // read before the first update
+        assert_eq!(settings(&app).substeps, 4);
+        update_until(&mut app, &|app| {
+            app.world()
+                .resource::<Assets<SimulationSettings>>()
+                .iter()
+                .next()
+                .is_some()
+        });
+        assert_eq!(settings(&app).tick_rate, 64.0);
+
+        std::fs::write(&path, "(tick_rate: 32.0)").unwrap();
+        update_until(&mut app, &|app| settings(app).tick_rate == 32.0);
+        assert_eq!(settings(&app).substeps, 1);
+        // which only takes effect at the start of the next frame
+        app.update();
+        assert_eq!(
+            app.world().resource::<Time<Fixed>>().timestep(),
+            Duration::from_secs_f64(1.0 / 32.0)
+        );
+
+        // a broken file shouldn't take the game down with it
+        std::fs::write(&path, "(tick_rate: ").unwrap();
+        update_until(&mut app, &|app| {
+            let handle = app
+                .world()
+                .resource::<SettingsFile>()
+                .handle
+                .clone()
+                .unwrap();
+            app.world()
+                .resource::<AssetServer>()
+                .load_state(&handle)
+                .is_failed()
+        });
+        assert_eq!(settings(&app).tick_rate, 32.0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
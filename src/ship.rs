@@ -1,20 +1,33 @@
-use crate::{GravityField, KeyBinds, KeyPair, Transform};
+use crate::{
+    GravityField, KeyBinds, KeyPair, SimulationSettings, TrajectoryNode, Transform, Velocity,
+    fragment::{self, Fragment},
+    input::{Inputs, LocalPlayer, Player},
+    kinematics::{AlignToVelocity, KinematicsSet},
+    mine::Mine,
+    rng::SimulationRng,
+    weapon::{self, Hit, Owner},
+};
 use bevy::prelude::*;
-use spacewar::TrajectoryNode;
+use serde::{Deserialize, Serialize};
 
-#[derive(Component, Clone, Debug)]
+#[derive(Component, Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+#[require(Health, Energy)]
 pub struct Ship {
-    pub velocity: Vec2,
     pub rotational_velocity: f32,
     pub sas: Option<SASMode>,
     pub draw_trajectory: usize,
     pub trajectory_gap: usize,
 }
 
+impl Ship {
+    /// rough radius of the hull, for anything that needs to hit a ship
+    pub const HIT_RADIUS: f32 = 15.0;
+}
+
 impl Default for Ship {
     fn default() -> Self {
         Self {
-            velocity: Vec2::X * 50.0,
             rotational_velocity: 0.0,
             sas: Some(SASMode::default()),
             draw_trajectory: 500,
@@ -23,7 +36,47 @@ impl Default for Ship {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// How fast prograde and retrograde SAS turn towards their target, in degrees per second for
+/// every degree off it. Any faster and they'd shake, any slower and they'd trail a long way
+/// behind prograde when swinging around a star close in.
+const SAS_TURN_RATE: f32 = 3.0;
+
+/// How fast turning picks up, by hand or under SAS, in degrees per second per second.
+const TURN_ACCELERATION: f32 = 64.0;
+
+/// How much of its spin stability SAS takes off each second.
+const STABILITY_DAMPING: f32 = 1.92;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Health(pub f32);
+
+impl Health {
+    pub const MAX: f32 = 100.0;
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self(Self::MAX)
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Energy(pub f32);
+
+impl Energy {
+    pub const MAX: f32 = 100.0;
+
+    /// per second
+    pub const RECHARGE_RATE: f32 = 10.0;
+}
+
+impl Default for Energy {
+    fn default() -> Self {
+        Self(Self::MAX)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub enum SASMode {
     #[default]
     Stability,
@@ -31,10 +84,21 @@ pub enum SASMode {
     Retrograde,
 }
 
-#[derive(Clone, Debug, Bundle, Default)]
+#[derive(Clone, Debug, Bundle)]
 pub struct Bundle {
     pub ship: Ship,
     pub transform: Transform,
+    pub velocity: Velocity,
+}
+
+impl Default for Bundle {
+    fn default() -> Self {
+        Self {
+            ship: Ship::default(),
+            transform: Transform::default(),
+            velocity: Velocity(Vec2::X * 50.0),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Resource, Bundle)]
@@ -75,110 +139,98 @@ fn spawn_ships(
 }
 
 fn change_speed(
-    ship: Single<(&mut Ship, &Transform)>,
-    keys: Res<ButtonInput<KeyCode>>,
-    keybinds: Res<KeyBinds>,
+    mut ships: Query<(&Player, &mut Velocity, &Transform), With<Ship>>,
+    inputs: Res<Inputs>,
+    settings: Res<SimulationSettings>,
+    time: Res<Time>,
 ) {
-    let (mut ship, transform) = ship.into_inner();
-
-    if keys.any_pressed(keybinds.accelerate()) {
-        ship.velocity += 0.5 * transform.local_x();
+    for (player, mut velocity, transform) in ships.iter_mut() {
+        if inputs.get(*player).thrust {
+            velocity.0 += settings.thrust * time.delta_secs() * transform.local_x();
+        }
     }
 }
 
-fn change_target(mut ship: Single<&mut Ship>, keys: Res<ButtonInput<KeyCode>>) {
-    if keys.pressed(KeyCode::KeyP) {
-        if let Some(sas) = ship.sas.as_mut() {
-            if *sas == SASMode::Prograde {
-                *sas = SASMode::Stability;
+fn change_target(mut ships: Query<(&Player, &mut Ship)>, inputs: Res<Inputs>) {
+    for (player, mut ship) in ships.iter_mut() {
+        let input = inputs.get(*player);
+
+        if input.toggle_prograde {
+            if let Some(sas) = ship.sas.as_mut() {
+                if *sas == SASMode::Prograde {
+                    *sas = SASMode::Stability;
+                } else {
+                    *sas = SASMode::Prograde;
+                }
+            }
+        } else if input.toggle_sas {
+            if ship.sas.is_none() {
+                ship.sas = Some(SASMode::Stability);
             } else {
-                *sas = SASMode::Prograde;
+                ship.sas = None;
             }
         }
-    } else if keys.just_pressed(KeyCode::KeyT) {
-        if ship.sas.is_none() {
-            ship.sas = Some(SASMode::Stability);
-        } else {
-            ship.sas = None;
-        }
     }
 }
 
 fn change_angle(
-    ship: Single<(&mut Ship, &Transform), With<Ship>>,
-    keys: Res<ButtonInput<KeyCode>>,
-    keybinds: Res<KeyBinds>,
+    ships: Query<(&mut Ship, &Transform, &Velocity, Option<&Player>)>,
+    inputs: Res<Inputs>,
+    time: Res<Time>,
 ) {
-    let (mut ship, transform) = ship.into_inner();
+    let max_step = TURN_ACCELERATION * time.delta_secs();
 
-    if keys.any_pressed(keybinds.rotation_speed().map(KeyPair::left)) {
-        ship.rotational_velocity += 1.0;
-    } else if keys.any_pressed(keybinds.rotation_speed().map(KeyPair::right)) {
-        ship.rotational_velocity -= 1.0;
-    }
+    for (mut ship, transform, velocity, player) in ships {
+        if let Some(player) = player {
+            ship.rotational_velocity += inputs.get(*player).turn as f32 * max_step;
+        }
 
-    if let Some(sas) = ship.sas {
-        match sas {
-            SASMode::Stability => ship.rotational_velocity -= 0.03 * ship.rotational_velocity,
-            SASMode::Prograde | SASMode::Retrograde => {
-                let mut target_heading = Rot2::radians(ship.velocity.to_angle());
-                if sas == SASMode::Retrograde {
-                    target_heading *= Rot2::PI;
-                };
-
-                let delta =
-                    target_heading.as_turn_fraction() - transform.rotation.as_turn_fraction();
-                ship.rotational_velocity += 0.16 * delta;
+        if let Some(sas) = ship.sas {
+            match sas {
+                SASMode::Stability => {
+                    ship.rotational_velocity -=
+                        STABILITY_DAMPING * time.delta_secs() * ship.rotational_velocity
+                }
+                SASMode::Prograde | SASMode::Retrograde => {
+                    let mut target_heading = Rot2::radians(velocity.0.to_angle());
+                    if sas == SASMode::Retrograde {
+                        target_heading *= Rot2::PI;
+                    };
+
+                    // the short way round, no harder than turning by hand, and never so fast that
+                    // it couldn't stop in time braking at half that
+                    let delta = transform.rotation.angle_to(target_heading).to_degrees();
+                    let stopping_speed = (TURN_ACCELERATION * delta.abs()).sqrt();
+                    let speed = (SAS_TURN_RATE * delta).clamp(-stopping_speed, stopping_speed);
+                    ship.rotational_velocity +=
+                        (speed - ship.rotational_velocity).clamp(-max_step, max_step);
+                }
             }
         }
     }
 }
 
-fn update_ship(
-    ship: Single<(&mut Ship, &mut Transform), With<Ship>>,
-    gravity: Res<GravityField>,
-    time: Res<Time>,
-) {
-    let (mut ship, mut ship_transform) = ship.into_inner();
-
-    ship_transform.rotation *= Rot2::degrees(ship.rotational_velocity * time.delta_secs());
-
-    let mut trajectory = gravity.trajectory_starting_at(
-        TrajectoryNode::from_translation_velocity(ship_transform.translation, ship.velocity),
-        time.delta_secs(),
-    );
-
-    dbg!(trajectory.periapsis_distance());
-    //trajectory.eccentricity();
-
-    let next_node = trajectory.next().unwrap();
-
-    ship.velocity = next_node.velocity();
-    ship_transform.translation = next_node.translation();
-
-    //trajectory.eccentricity();
-
-    /*     dbg!(ship.velocity.length());
-    dbg!(ship_transform.translation.length());
-    println!(
-        "v2 / r = {}",
-        ship.velocity.length_squared() / ship_transform.translation.length()
-    ); */
+fn update_ship(ships: Query<(&Ship, &mut Transform)>, time: Res<Time>) {
+    for (ship, mut transform) in ships {
+        transform.rotation *= Rot2::degrees(ship.rotational_velocity * time.delta_secs());
+    }
 }
 
 fn draw_trajectory(
-    ships: Query<(&Ship, &Transform)>,
+    ships: Query<(&Ship, &Transform, &Velocity)>,
     gravity: Res<GravityField>,
     mut gizmos: Gizmos,
     time: Res<Time<Fixed>>,
+    settings: Res<SimulationSettings>,
 ) {
-    for (ship, transform) in ships.iter() {
+    for (ship, transform, velocity) in ships.iter() {
         let trajectory = gravity.trajectory_starting_at(
-            TrajectoryNode::from_translation_velocity(transform.translation, ship.velocity),
+            TrajectoryNode::from_translation_velocity(transform.translation, velocity.0),
             time.delta_secs(),
         );
 
         for (i, node) in trajectory
+            .take(settings.trajectory_steps)
             .step_by(ship.trajectory_gap)
             .take(ship.draw_trajectory)
             .enumerate()
@@ -193,44 +245,153 @@ fn draw_trajectory(
     }
 }
 
-fn fire_missile(mut commands: Commands, ship: Single<(&Ship, &Transform)>) {
-    commands.spawn(crate::missile::Bundle {
-        transform: *ship.1,
-        missile: crate::Missile {
-            speed: ship.0.velocity.length() + 2.0,
-            ..Default::default()
-        },
-    });
+fn fire_missile(
+    mut commands: Commands,
+    ships: Query<(Entity, &Player, &Transform, &Velocity), With<Ship>>,
+    inputs: Res<Inputs>,
+) {
+    const LAUNCH_SPEED: f32 = 150.0;
+
+    for (id, player, transform, velocity) in ships.iter() {
+        if !inputs.get(*player).fire_missile {
+            continue;
+        }
+
+        commands.spawn((
+            crate::missile::Bundle {
+                transform: *transform,
+                velocity: Velocity(velocity.0 + LAUNCH_SPEED * transform.local_x()),
+                ..Default::default()
+            },
+            AlignToVelocity,
+            Owner(id),
+        ));
+    }
+}
+
+fn deploy_mine(
+    mut commands: Commands,
+    ships: Query<(Entity, &Player, &Transform, &Velocity), With<Ship>>,
+    mines: Query<&Owner, With<Mine>>,
+    inputs: Res<Inputs>,
+) {
+    for (id, player, transform, velocity) in ships.iter() {
+        if !inputs.get(*player).deploy_mine || !weapon::can_launch::<Mine>(id, &mines) {
+            continue;
+        }
+
+        commands.spawn((
+            crate::mine::Bundle {
+                transform: Transform::default()
+                    .with_translation(transform.translation)
+                    .with_z_layer(transform.z_layer - 1.0),
+                velocity: *velocity,
+                ..Default::default()
+            },
+            Owner(id),
+        ));
+    }
+}
+
+fn recharge(mut ships: Query<&mut Energy, With<Ship>>, time: Res<Time>) {
+    for mut energy in ships.iter_mut() {
+        energy.0 = (energy.0 + Energy::RECHARGE_RATE * time.delta_secs()).min(Energy::MAX);
+    }
+}
+
+fn take_damage(
+    mut commands: Commands,
+    mut hits: EventReader<Hit>,
+    mut ships: Query<(&Transform, &Velocity, &mut Health), With<Ship>>,
+    mut rng: ResMut<SimulationRng>,
+) {
+    const DEBRIS_COUNT: usize = 12;
+    const DEBRIS_SPREAD: f32 = 25.0;
+
+    // in a set order, however they were sent, since the order decides what's left of a ship
+    let mut hits: Vec<&Hit> = hits.read().collect();
+    hits.sort_by_key(|hit| (hit.target, hit.damage.to_bits()));
+
+    for hit in hits {
+        let Ok((transform, velocity, mut health)) = ships.get_mut(hit.target) else {
+            continue;
+        };
+
+        // already destroyed by an earlier hit this tick
+        if health.0 <= 0.0 {
+            continue;
+        }
+
+        health.0 -= hit.damage;
+
+        if health.0 <= 0.0 {
+            commands.spawn_batch(fragment::scatter(
+                transform.translation,
+                velocity.0,
+                Fragment {
+                    damage: 20.0,
+                    lifetime: 60.0,
+                    ..Default::default()
+                },
+                DEBRIS_SPREAD,
+                DEBRIS_COUNT,
+                &mut rng.0,
+            ));
+
+            commands.entity(hit.target).despawn();
+        }
+    }
 }
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Sprite>()
-            .add_systems(PostStartup, spawn_ships)
+        app.register_type::<Ship>()
+            .init_resource::<Inputs>()
+            .init_resource::<LocalPlayer>()
+            .init_resource::<SimulationRng>()
             .add_systems(
                 Update,
+                // still useful for looking ahead while paused
+                trajectory_drawing_keybinds,
+            )
+            .add_systems(
+                FixedUpdate,
                 (
                     change_target,
-                    change_speed,
                     change_angle,
-                    trajectory_drawing_keybinds,
-                    fire_missile.run_if(bevy::input::common_conditions::input_just_pressed(
-                        KeyCode::Space,
-                    )),
-                ),
+                    update_ship,
+                    change_speed,
+                    recharge,
+                    fire_missile,
+                    deploy_mine,
+                )
+                    .chain()
+                    .before(KinematicsSet),
             )
-            .add_systems(FixedUpdate, (update_ship, trail::update_trail))
-            .add_systems(PostUpdate, (draw_trajectory, spawn_ships));
+            // once everything that could have hit something this tick has
+            .add_systems(FixedPostUpdate, take_damage);
+
+        if crate::has_renderer(app) {
+            app.init_resource::<Sprite>()
+                .add_systems(PostStartup, spawn_ships)
+                .add_observer(trail::despawn_trail)
+                .add_systems(FixedUpdate, trail::update_trail)
+                .add_systems(PostUpdate, (draw_trajectory, spawn_ships));
+        }
     }
 }
 
 pub fn trajectory_drawing_keybinds(
-    mut ship: Single<&mut Ship>,
+    mut ships: Query<(&mut Ship, &Player)>,
+    local: Res<LocalPlayer>,
     keys: Res<ButtonInput<KeyCode>>,
     keybinds: Res<KeyBinds>,
 ) {
+    let Some((mut ship, _)) = ships.iter_mut().find(|(_, player)| **player == local.0) else {
+        return;
+    };
     let shift = keys.pressed(KeyCode::ShiftLeft);
 
     if keys.any_pressed(keybinds.trajectory_length().map(KeyPair::shorter)) {
@@ -253,22 +414,29 @@ pub fn trajectory_drawing_keybinds(
 }
 
 mod trail {
-    const TRAIL_LENGTH: usize = 20;
-
     use super::Transform;
+    use crate::SimulationSettings;
+    use crate::transform2d::NoInterpolation;
     use bevy::prelude::*;
     use std::collections::VecDeque;
 
-    #[derive(Component, Clone, Debug)]
+    #[derive(Component, Clone, Debug, Default)]
     pub struct Trail(VecDeque<Entity>);
 
     #[derive(Component, Clone, Debug)]
-
     pub struct TrailNode;
 
-    impl Default for Trail {
-        fn default() -> Self {
-            Self(VecDeque::with_capacity(TRAIL_LENGTH))
+    /// The nodes aren't children of the ship, since they stay where it's been, so they have to
+    /// go along with it by hand.
+    pub fn despawn_trail(
+        trigger: Trigger<OnRemove, Trail>,
+        trails: Query<&Trail>,
+        mut commands: Commands,
+    ) {
+        if let Ok(trail) = trails.get(trigger.target()) {
+            for node in &trail.0 {
+                commands.entity(*node).try_despawn();
+            }
         }
     }
 
@@ -281,12 +449,21 @@ mod trail {
             (&mut Transform, &mut MeshMaterial2d<ColorMaterial>),
             Without<crate::Ship>,
         >,
+        settings: Res<SimulationSettings>,
     ) {
+        let length = settings.trail_length;
+
         let mesh = meshes.add(Circle::new(2.0));
         let default_material = materials.add(Color::WHITE);
 
         for (mut trail, ship_transform) in trails.iter_mut() {
-            while trail.0.len() < TRAIL_LENGTH {
+            while trail.0.len() > length {
+                if let Some(node) = trail.0.pop_back() {
+                    commands.entity(node).despawn();
+                }
+            }
+
+            while trail.0.len() < length {
                 let id = commands
                     .spawn((
                         Transform::default()
@@ -294,6 +471,9 @@ mod trail {
                             .with_z_layer(ship_transform.z_layer - 1.0),
                         Mesh2d(mesh.clone()),
                         MeshMaterial2d(default_material.clone()),
+                        TrailNode,
+                        // trail nodes get recycled from the back to the front every tick
+                        NoInterpolation,
                     ))
                     .id();
                 trail.0.push_back(id);
@@ -313,12 +493,29 @@ mod trail {
             let mut i = 0;
             while let Some((_, mut material)) = iter.fetch_next() {
                 const COLOR: Color = Color::oklch(1.0, 0.8, 0.0);
-                material.0 = materials.add(COLOR.with_alpha(1.0 - i as f32 / TRAIL_LENGTH as f32));
+                material.0 = materials.add(COLOR.with_alpha(1.0 - i as f32 / length as f32));
                 i += 1;
             }
         }
     }
 
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn trail_goes_with_its_ship() {
+            let mut world = World::new();
+            world.add_observer(despawn_trail);
+            let nodes: VecDeque<Entity> = (0..3).map(|_| world.spawn(TrailNode).id()).collect();
+            let ship = world.spawn(Trail(nodes)).id();
+
+            world.despawn(ship);
+            world.flush();
+            assert_eq!(world.query::<&TrailNode>().iter(&world).count(), 0);
+        }
+    }
+
     /*
     pub fn draw_trail(trails: Query<&Trail>, mut gizmos: Gizmos) {
         for trail in trails {
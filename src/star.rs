@@ -1,9 +1,14 @@
-use crate::{GravityField, Mass, Transform};
+use crate::{
+    GravityField, Mass, Transform, kepler::Orbit, kinematics::SimulationTime, rewind::Restored,
+};
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Component, Clone, Copy, Debug)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
 pub struct Star {
     pub mass: f32,
+    pub radius: f32,
 }
 
 #[derive(Clone, Debug, Resource, Bundle)]
@@ -21,39 +26,200 @@ impl FromWorld for StarSprite {
     }
 }
 
-#[derive(Clone, Debug, Bundle)]
+#[derive(Clone, Debug, Bundle, Serialize, Deserialize)]
 pub struct Bundle {
     pub transform: Transform,
     pub star: Star,
 }
 
+/// Moves a star along an orbit around another one, worked out from [`SimulationTime`] rather than
+/// integrated so that it never drifts off.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Orbiting {
+    pub around: Entity,
+    /// as it was at the start of the match
+    pub orbit: Orbit,
+}
+
+fn follow_orbits(
+    orbiting: Query<(Entity, &Orbiting)>,
+    mut transforms: Query<&mut Transform, With<Star>>,
+    time: Res<SimulationTime>,
+) {
+    let positions: Vec<(Entity, Vec2)> = orbiting
+        .iter()
+        .map(|(entity, _)| {
+            // relative to each parent in turn, out to one that stays put
+            let mut position = Vec2::ZERO;
+            let mut current = entity;
+            while let Ok((_, orbiting)) = orbiting.get(current) {
+                position += orbiting.orbit.after(time.0).state().0;
+                current = orbiting.around;
+            }
+
+            let root = transforms
+                .get(current)
+                .map_or(Vec2::ZERO, |t| t.translation);
+            (entity, root + position)
+        })
+        .collect();
+
+    for (entity, position) in positions {
+        if let Ok(mut transform) = transforms.get_mut(entity)
+            && transform.translation != position
+        {
+            transform.translation = position;
+        }
+    }
+}
+
 fn spawn_stars(
     mut commands: Commands,
-    stars: Query<Entity, Added<Star>>,
-    mut components: Query<(&mut Transform, &Star)>,
+    stars: Query<(Entity, &Star, &mut Transform), Added<Star>>,
     sprite: Res<StarSprite>,
-    mut gravity: ResMut<GravityField>,
 ) {
-    for entity in stars.iter() {
-        let (mut transform, star) = components.get_mut(entity).unwrap();
+    for (entity, star, mut transform) in stars {
+        transform.scale = Vec2::splat(star.radius);
 
-        transform.scale = Vec2::splat(10.0);
+        commands.entity(entity).insert(sprite.clone());
+    }
+}
 
-        gravity.masses.push(Mass {
-            translation: transform.translation,
-            mass: star.mass,
-        });
+pub(crate) fn sync_gravity(
+    stars: Query<(Entity, Ref<Star>, Ref<Transform>)>,
+    mut gravity: ResMut<GravityField>,
+) {
+    for (entity, star, transform) in stars.iter() {
+        if !star.is_changed() && !transform.is_changed() {
+            continue;
+        }
 
-        commands.entity(entity).insert(sprite.clone());
+        gravity.insert(
+            entity,
+            Mass {
+                translation: transform.translation,
+                mass: star.mass,
+            },
+        );
     }
 }
 
+fn caught_up(mut commands: Commands) {
+    commands.remove_resource::<Restored>();
+}
+
+fn remove_from_gravity(trigger: Trigger<OnRemove, Star>, mut gravity: ResMut<GravityField>) {
+    gravity.remove(trigger.target());
+}
+
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<StarSprite>()
-            .add_systems(PostStartup, spawn_stars)
-            .add_systems(FixedPostUpdate, spawn_stars);
+        app.register_type::<Star>()
+            .register_type::<GravityField>()
+            .init_resource::<GravityField>()
+            .init_resource::<SimulationTime>()
+            .add_observer(remove_from_gravity)
+            .add_systems(FixedPreUpdate, (follow_orbits, sync_gravity).chain())
+            // catching up after a rewind, which doesn't wait for the next tick
+            .add_systems(
+                Update,
+                (follow_orbits, sync_gravity, caught_up)
+                    .chain()
+                    .run_if(resource_exists::<Restored>),
+            );
+
+        if crate::has_renderer(app) {
+            app.init_resource::<StarSprite>()
+                .add_systems(Update, spawn_stars)
+                .add_systems(FixedPostUpdate, spawn_stars);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity::GRAVITATIONAL_CONSTANT;
+    use bevy::time::TimeUpdateStrategy;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, Plugin))
+            // exactly one fixed tick per update
+            .insert_resource(TimeUpdateStrategy::ManualDuration(
+                Time::<Fixed>::default().timestep(),
+            ));
+        app
+    }
+
+    fn acceleration_at(app: &App, point: Vec2) -> Vec2 {
+        app.world()
+            .resource::<GravityField>()
+            .acceleration_at(point)
+    }
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) <= b.length() * 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn follows_star_entities() {
+        const MASS: f32 = 1.6e16;
+        let mut app = app();
+
+        let star = app
+            .world_mut()
+            .spawn(Bundle {
+                star: Star {
+                    mass: MASS,
+                    radius: 10.0,
+                },
+                transform: Transform::default(),
+            })
+            .id();
+        app.update();
+        app.update();
+
+        let point = Vec2::new(100.0, 0.0);
+        assert_close(
+            acceleration_at(&app, point),
+            -Vec2::X * GRAVITATIONAL_CONSTANT * MASS / 100.0_f32.powi(2),
+        );
+
+        app.world_mut()
+            .get_mut::<Transform>(star)
+            .unwrap()
+            .translation = Vec2::new(50.0, 0.0);
+        app.update();
+        assert_close(
+            acceleration_at(&app, point),
+            -Vec2::X * GRAVITATIONAL_CONSTANT * MASS / 50.0_f32.powi(2),
+        );
+
+        app.world_mut().get_mut::<Star>(star).unwrap().mass = MASS * 2.0;
+        app.update();
+        assert_close(
+            acceleration_at(&app, point),
+            -Vec2::X * GRAVITATIONAL_CONSTANT * MASS * 2.0 / 50.0_f32.powi(2),
+        );
+        assert_eq!(app.world().resource::<GravityField>().masses().len(), 1);
+
+        app.world_mut().despawn(star);
+        app.update();
+        assert_eq!(acceleration_at(&app, point), Vec2::ZERO);
+        assert_eq!(app.world().resource::<GravityField>().masses().len(), 0);
+    }
+
+    #[test]
+    fn gravity_is_reflected() {
+        let app = app();
+        let registry = app.world().resource::<AppTypeRegistry>().read();
+        let reflect = registry
+            .get_type_data::<ReflectResource>(std::any::TypeId::of::<GravityField>())
+            .unwrap();
+        assert!(reflect.reflect(app.world()).is_ok());
+        assert!(registry.get(std::any::TypeId::of::<Star>()).is_some());
     }
 }
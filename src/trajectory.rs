@@ -1,5 +1,6 @@
 use crate::GravityField;
 use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Trajectory<'g> {
@@ -100,7 +101,7 @@ impl DoubleEndedIterator for Trajectory<'_> {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct TrajectoryNode {
     translation: Vec2,
     velocity: Vec2,
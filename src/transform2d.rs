@@ -0,0 +1,541 @@
+use bevy::{math::Affine2, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::ops::Mul;
+
+/// Position, rotation and scale of an entity relative to its parent, or to the world if it
+/// doesn't have one.
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+#[require(GlobalTransform2d)]
+pub struct Transform2d {
+    pub translation: Vec2,
+    pub z_layer: f32,
+    pub rotation: Rot2,
+    pub scale: Vec2,
+}
+
+impl Default for Transform2d {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform2d {
+    pub const IDENTITY: Self = Self {
+        translation: Vec2::ZERO,
+        z_layer: 0.0,
+        rotation: Rot2::IDENTITY,
+        scale: Vec2::ONE,
+    };
+
+    pub fn with_translation(mut self, value: Vec2) -> Self {
+        self.translation = value;
+        self
+    }
+
+    pub fn with_z_layer(mut self, value: f32) -> Self {
+        self.z_layer = value;
+        self
+    }
+
+    pub fn with_rotation(mut self, value: Rot2) -> Self {
+        self.rotation = value;
+        self
+    }
+
+    pub fn with_scale(mut self, value: Vec2) -> Self {
+        self.scale = value;
+        self
+    }
+
+    pub fn local_y(&self) -> Dir2 {
+        // Rot2 * unit vector is length 1
+        Dir2::new_unchecked(self.rotation * Vec2::Y)
+    }
+
+    pub fn local_x(&self) -> Dir2 {
+        // Rot2 * unit vector is length 1
+        Dir2::new_unchecked(self.rotation * Vec2::X)
+    }
+
+    /// Scale, then rotate, then translate.
+    pub fn compute_affine(&self) -> Affine2 {
+        Affine2::from_scale_angle_translation(
+            self.scale,
+            self.rotation.as_radians(),
+            self.translation,
+        )
+    }
+
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        self.translation + self.transform_vector(point)
+    }
+
+    /// Like [`transform_point`](Self::transform_point), but ignoring translation.
+    pub fn transform_vector(&self, vector: Vec2) -> Vec2 {
+        self.rotation * (self.scale * vector)
+    }
+
+    /// Somewhere between `self` and `other`, with `t` going from `0.0` to `1.0`.
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            z_layer: other.z_layer,
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+
+    /// Only exact for uniform scale, see [`GlobalTransform2d::inverse`] otherwise.
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let scale = self.scale.recip();
+
+        Self {
+            translation: -(scale * (rotation * self.translation)),
+            z_layer: -self.z_layer,
+            rotation,
+            scale,
+        }
+    }
+}
+
+/// `child` placed relative to `self`, e.g. where a child entity ends up given its parent.
+///
+/// Like Bevy's `Transform`, this is only exact when `self` has uniform scale or `child` has no
+/// rotation, since non-uniform scale followed by rotation is a shear. [`GlobalTransform2d`]
+/// handles that case properly.
+impl Mul for Transform2d {
+    type Output = Self;
+
+    fn mul(self, child: Self) -> Self {
+        Self {
+            translation: self.transform_point(child.translation),
+            z_layer: self.z_layer + child.z_layer,
+            rotation: self.rotation * child.rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+}
+
+/// Where an entity actually is in the world, after applying the [`Transform2d`] of every
+/// ancestor through [`ChildOf`].
+///
+/// Updated at the end of every fixed tick, so it lags behind any changes made in between.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct GlobalTransform2d {
+    affine: Affine2,
+    z_layer: f32,
+}
+
+impl Default for GlobalTransform2d {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl From<Transform2d> for GlobalTransform2d {
+    fn from(transform: Transform2d) -> Self {
+        Self {
+            affine: transform.compute_affine(),
+            z_layer: transform.z_layer,
+        }
+    }
+}
+
+impl GlobalTransform2d {
+    pub const IDENTITY: Self = Self {
+        affine: Affine2::IDENTITY,
+        z_layer: 0.0,
+    };
+
+    pub fn affine(&self) -> Affine2 {
+        self.affine
+    }
+
+    pub fn translation(&self) -> Vec2 {
+        self.affine.translation
+    }
+
+    pub fn z_layer(&self) -> f32 {
+        self.z_layer
+    }
+
+    pub fn rotation(&self) -> Rot2 {
+        self.compute_transform().rotation
+    }
+
+    pub fn scale(&self) -> Vec2 {
+        self.compute_transform().scale
+    }
+
+    pub fn local_x(&self) -> Dir2 {
+        self.compute_transform().local_x()
+    }
+
+    pub fn local_y(&self) -> Dir2 {
+        self.compute_transform().local_y()
+    }
+
+    /// Closest [`Transform2d`], which loses any shear picked up from non-uniform scaling.
+    pub fn compute_transform(&self) -> Transform2d {
+        let (scale, angle, translation) = self.affine.to_scale_angle_translation();
+
+        Transform2d {
+            translation,
+            z_layer: self.z_layer,
+            rotation: Rot2::radians(angle),
+            scale,
+        }
+    }
+
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        self.affine.transform_point2(point)
+    }
+
+    /// Like [`transform_point`](Self::transform_point), but ignoring translation.
+    pub fn transform_vector(&self, vector: Vec2) -> Vec2 {
+        self.affine.transform_vector2(vector)
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            affine: self.affine.inverse(),
+            z_layer: -self.z_layer,
+        }
+    }
+}
+
+impl Mul for GlobalTransform2d {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            affine: self.affine * rhs.affine,
+            z_layer: self.z_layer + rhs.z_layer,
+        }
+    }
+}
+
+impl Mul<Transform2d> for GlobalTransform2d {
+    type Output = Self;
+
+    fn mul(self, rhs: Transform2d) -> Self {
+        self * Self::from(rhs)
+    }
+}
+
+fn propagate_global_transforms(
+    roots: Query<(Entity, &Transform2d, Option<&Children>), Without<ChildOf>>,
+    locals: Query<(&Transform2d, Option<&Children>), With<ChildOf>>,
+    mut globals: Query<&mut GlobalTransform2d>,
+) {
+    fn propagate(
+        parent: GlobalTransform2d,
+        children: &Children,
+        locals: &Query<(&Transform2d, Option<&Children>), With<ChildOf>>,
+        globals: &mut Query<&mut GlobalTransform2d>,
+    ) {
+        for child in children.iter() {
+            let Ok((transform, grandchildren)) = locals.get(child) else {
+                continue;
+            };
+
+            let global = parent * *transform;
+            if let Ok(mut child_global) = globals.get_mut(child) {
+                child_global.set_if_neq(global);
+            }
+
+            if let Some(grandchildren) = grandchildren {
+                propagate(global, grandchildren, locals, globals);
+            }
+        }
+    }
+
+    for (root, transform, children) in roots.iter() {
+        let global = GlobalTransform2d::from(*transform);
+        if let Ok(mut root_global) = globals.get_mut(root) {
+            root_global.set_if_neq(global);
+        }
+
+        if let Some(children) = children {
+            propagate(global, children, &locals, &mut globals);
+        }
+    }
+}
+
+/// Where an entity was at the start of the current fixed tick, so rendering can smoothly
+/// interpolate towards where it is now.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct PreviousTransform2d(pub Transform2d);
+
+/// Always render an entity exactly where it is, e.g. for anything that jumps around every tick.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct NoInterpolation;
+
+/// Skip interpolation for an entity until the next fixed tick, so it doesn't visibly slide
+/// across the screen after being moved somewhere new.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct Teleported;
+
+fn snapshot_previous_transforms(
+    mut commands: Commands,
+    mut query: Query<
+        (Entity, &Transform2d, Option<&mut PreviousTransform2d>),
+        Without<NoInterpolation>,
+    >,
+) {
+    for (entity, transform, previous) in query.iter_mut() {
+        match previous {
+            Some(mut previous) => {
+                previous.set_if_neq(PreviousTransform2d(*transform));
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(PreviousTransform2d(*transform));
+            }
+        }
+    }
+}
+
+fn teleport(trigger: Trigger<OnAdd, Teleported>, mut commands: Commands) {
+    // with nothing to interpolate from, it gets rendered where it is until the next snapshot
+    commands
+        .entity(trigger.target())
+        .remove::<(PreviousTransform2d, Teleported)>();
+}
+
+#[allow(clippy::type_complexity)]
+fn transform2d_to_bevy_transform(
+    mut query: Query<(
+        Ref<Transform2d>,
+        Option<Ref<PreviousTransform2d>>,
+        &mut Transform,
+    )>,
+    time: Res<Time<Fixed>>,
+    virtual_time: Res<Time<Virtual>>,
+) {
+    // while paused, show exactly where stepping has got to
+    let fraction = if virtual_time.is_paused() {
+        1.0
+    } else {
+        time.overstep_fraction()
+    };
+
+    for (current, previous, mut bevy_transform) in query.iter_mut() {
+        // anything that hasn't moved since last frame is already where it should be
+        let settled = previous
+            .as_ref()
+            .is_none_or(|previous| !previous.is_changed() && previous.0 == *current);
+        if settled && !current.is_changed() {
+            continue;
+        }
+
+        let transform2d = match previous {
+            Some(previous) => previous.0.interpolate(&current, fraction),
+            None => *current,
+        };
+
+        let translation = transform2d.translation.extend(transform2d.z_layer);
+        let rotation = Quat::from_rotation_z(transform2d.rotation.as_radians());
+        let scale = transform2d.scale.extend(1.0);
+
+        bevy_transform.set_if_neq(Transform {
+            translation,
+            rotation,
+            scale,
+        });
+    }
+}
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Transform2d>()
+            .add_systems(PostStartup, propagate_global_transforms)
+            .add_observer(teleport)
+            .add_systems(FixedFirst, snapshot_previous_transforms)
+            .add_systems(FixedPostUpdate, propagate_global_transforms)
+            .add_systems(
+                PostUpdate,
+                transform2d_to_bevy_transform.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn nested_rotation_and_scale() {
+        let parent = Transform2d::default()
+            .with_translation(Vec2::new(10.0, 0.0))
+            .with_rotation(Rot2::radians(FRAC_PI_2))
+            .with_scale(Vec2::splat(2.0));
+        let child = Transform2d::default()
+            .with_translation(Vec2::X)
+            .with_rotation(Rot2::radians(FRAC_PI_2));
+        let grandchild = Transform2d::default()
+            .with_translation(Vec2::X)
+            .with_scale(Vec2::splat(0.5));
+
+        let global = GlobalTransform2d::from(parent) * child * grandchild;
+
+        // parent turns +x into +y and doubles it, child turns it again into -x
+        assert_close(global.translation(), Vec2::new(8.0, 2.0));
+        assert_close(*global.local_x(), -Vec2::X);
+        assert_close(global.scale(), Vec2::ONE);
+        assert_close(
+            (parent * child * grandchild).translation,
+            global.translation(),
+        );
+    }
+
+    #[test]
+    fn inverse_round_trip() {
+        let transform = Transform2d::default()
+            .with_translation(Vec2::new(-3.0, 7.0))
+            .with_rotation(Rot2::degrees(30.0))
+            .with_scale(Vec2::new(2.0, 0.5));
+        let global = GlobalTransform2d::from(transform);
+        let point = Vec2::new(4.0, -1.0);
+
+        assert_close(
+            global
+                .inverse()
+                .transform_point(global.transform_point(point)),
+            point,
+        );
+        assert_close(
+            transform.transform_point(point),
+            global.transform_point(point),
+        );
+        assert_close((global * global.inverse()).transform_point(point), point);
+
+        let uniform = transform.with_scale(Vec2::splat(3.0));
+        assert_close(
+            uniform
+                .inverse()
+                .transform_point(uniform.transform_point(point)),
+            point,
+        );
+    }
+
+    #[test]
+    fn propagates_through_hierarchy() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, Plugin));
+
+        let parent = app
+            .world_mut()
+            .spawn(
+                Transform2d::default()
+                    .with_translation(Vec2::new(100.0, 0.0))
+                    .with_rotation(Rot2::radians(FRAC_PI_2))
+                    .with_scale(Vec2::splat(10.0)),
+            )
+            .id();
+        let child = app
+            .world_mut()
+            .spawn((
+                Transform2d::default()
+                    .with_translation(Vec2::X)
+                    .with_rotation(Rot2::radians(FRAC_PI_2))
+                    .with_scale(Vec2::splat(0.5)),
+                ChildOf(parent),
+            ))
+            .id();
+        let grandchild = app
+            .world_mut()
+            .spawn((
+                Transform2d::default().with_translation(Vec2::X * 2.0),
+                ChildOf(child),
+            ))
+            .id();
+
+        app.update();
+
+        let global = |entity| *app.world().get::<GlobalTransform2d>(entity).unwrap();
+
+        assert_close(global(parent).translation(), Vec2::new(100.0, 0.0));
+        assert_close(global(child).translation(), Vec2::new(100.0, 10.0));
+        assert_close(global(grandchild).translation(), Vec2::new(90.0, 10.0));
+        assert_close(*global(grandchild).local_x(), -Vec2::X);
+        assert_close(global(grandchild).scale(), Vec2::splat(5.0));
+    }
+
+    #[test]
+    fn leaves_still_transforms_alone() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, Plugin));
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform2d::default().with_translation(Vec2::new(3.0, 4.0)),
+                Transform::default(),
+            ))
+            .id();
+
+        app.update();
+        app.update();
+        let changed = |app: &App| {
+            app.world()
+                .entity(entity)
+                .get_change_ticks::<Transform>()
+                .unwrap()
+                .changed
+        };
+        let before = changed(&app);
+        assert_eq!(
+            app.world().get::<Transform>(entity).unwrap().translation,
+            Vec3::new(3.0, 4.0, 0.0)
+        );
+
+        app.update();
+        app.update();
+        assert_eq!(changed(&app), before);
+
+        app.world_mut()
+            .get_mut::<Transform2d>(entity)
+            .unwrap()
+            .translation = Vec2::new(5.0, 6.0);
+        app.update();
+        assert_ne!(changed(&app), before);
+    }
+
+    #[test]
+    fn teleporting_skips_interpolation() {
+        let mut app = App::new();
+        // no fixed ticks, and no overstep, so anything interpolating is drawn where it was
+        app.add_plugins(Plugin)
+            .init_resource::<Time<Fixed>>()
+            .init_resource::<Time<Virtual>>();
+        let start = Transform2d::default();
+        let entity = app
+            .world_mut()
+            .spawn((start, PreviousTransform2d(start), Transform::default()))
+            .id();
+        let translation = |app: &App| app.world().get::<Transform>(entity).unwrap().translation;
+
+        app.world_mut()
+            .get_mut::<Transform2d>(entity)
+            .unwrap()
+            .translation = Vec2::X;
+        app.update();
+        assert_eq!(translation(&app), Vec3::ZERO);
+
+        app.world_mut()
+            .entity_mut(entity)
+            .insert((start.with_translation(Vec2::Y * 100.0), Teleported));
+        app.update();
+        assert_eq!(translation(&app), Vec3::new(0.0, 100.0, 0.0));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    GlobalTransform2d, GravityField, Missile, Ship, TrajectoryNode, Transform, Velocity,
+    kinematics::{AlignToVelocity, KinematicsSet},
+    weapon::{Owner, Weapon},
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const RANGE: f32 = 400.0;
+
+/// degrees per second
+const SLEW_RATE: f32 = 360.0;
+
+/// seconds between shots
+const COOLDOWN: f32 = 0.25;
+
+/// how far off target, in degrees, the turret will still fire
+const FIRING_ARC: f32 = 5.0;
+
+const INTERCEPTOR_SPEED: f32 = 600.0;
+
+/// distance at which an interceptor takes out a missile
+const KILL_RADIUS: f32 = 12.0;
+
+/// Mounts a point-defense turret on a ship.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct PointDefense;
+
+/// The turret itself, spawned as a child of a ship with [`PointDefense`].
+#[derive(Component, Clone, Debug, Default)]
+pub struct Turret {
+    pub cooldown: f32,
+    pub target: Option<Entity>,
+}
+
+/// A short-lived round fired by a [`Turret`] that destroys missiles on contact.
+#[derive(Clone, Debug, PartialEq, Component, Default, TypePath, Serialize, Deserialize)]
+pub struct Interceptor {
+    pub age: f32,
+}
+
+impl Weapon for Interceptor {
+    const MAX_AGE: f32 = 1.0;
+
+    fn age(&self) -> f32 {
+        self.age
+    }
+
+    fn age_mut(&mut self) -> &mut f32 {
+        &mut self.age
+    }
+
+    fn color(&self) -> Color {
+        Color::oklch(0.9, 0.1, 150.0)
+    }
+}
+
+#[derive(Clone, Debug, Resource)]
+struct Sprites {
+    turret: (Mesh2d, MeshMaterial2d<ColorMaterial>),
+    interceptor: (Mesh2d, MeshMaterial2d<ColorMaterial>),
+}
+
+impl FromWorld for Sprites {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            turret: (
+                Mesh2d(world.add_asset(Rectangle::new(1.0, 0.4))),
+                MeshMaterial2d(world.add_asset(Color::oklch(0.7, 0.05, 150.0))),
+            ),
+            interceptor: (
+                Mesh2d(world.add_asset(Circle::new(0.5))),
+                MeshMaterial2d(world.add_asset(Color::WHITE)),
+            ),
+        }
+    }
+}
+
+fn mount_turrets(mut commands: Commands, ships: Query<Entity, Added<PointDefense>>) {
+    for ship in ships.iter() {
+        commands.spawn((
+            Turret::default(),
+            // relative to the ship, which is already scaled up
+            Transform::default()
+                .with_z_layer(1.0)
+                .with_scale(Vec2::splat(0.5)),
+            ChildOf(ship),
+        ));
+    }
+}
+
+fn spawn_turrets(
+    mut commands: Commands,
+    turrets: Query<Entity, Added<Turret>>,
+    sprites: Res<Sprites>,
+) {
+    for entity in turrets.iter() {
+        commands.entity(entity).insert(sprites.turret.clone());
+    }
+}
+
+fn spawn_interceptors(
+    mut commands: Commands,
+    interceptors: Query<Entity, Added<Interceptor>>,
+    mut components: Query<&mut Transform>,
+    sprites: Res<Sprites>,
+) {
+    for entity in interceptors.iter() {
+        let mut transform = components.get_mut(entity).unwrap();
+
+        transform.scale = Vec2::splat(4.0);
+
+        commands.entity(entity).insert(sprites.interceptor.clone());
+    }
+}
+
+/// Where to aim to hit a missile, following its trajectory until an interceptor fired from
+/// `origin` could get there in time.
+fn lead(
+    origin: Vec2,
+    missile: (&Transform, &Velocity),
+    gravity: &GravityField,
+    tick_secs: f32,
+) -> Option<Vec2> {
+    let (transform, velocity) = missile;
+    let max_ticks = (RANGE / INTERCEPTOR_SPEED / tick_secs).ceil() as usize;
+
+    gravity
+        .trajectory_starting_at(
+            TrajectoryNode::from_translation_velocity(transform.translation, velocity.0),
+            tick_secs,
+        )
+        .take(max_ticks)
+        .enumerate()
+        .map(|(tick, node)| (tick as f32 * tick_secs, node.translation()))
+        .find(|(time, point)| origin.distance(*point) / INTERCEPTOR_SPEED <= *time)
+        .map(|(_, point)| point)
+}
+
+fn acquire_targets(
+    mut turrets: Query<(&mut Turret, &ChildOf)>,
+    ships: Query<&Transform, With<Ship>>,
+    missiles: Query<(Entity, &Transform, &Velocity, Option<&Owner>), With<Missile>>,
+) {
+    for (mut turret, child_of) in turrets.iter_mut() {
+        let Ok(ship_transform) = ships.get(child_of.parent()) else {
+            continue;
+        };
+
+        // most threatening is whichever hostile missile would reach the ship first
+        turret.target = missiles
+            .iter()
+            .filter(|(.., owner)| owner.is_none_or(|owner| owner.0 != child_of.parent()))
+            .filter_map(|(id, transform, velocity, _)| {
+                let offset = ship_transform.translation - transform.translation;
+                let distance = offset.length();
+                let closing_speed = velocity.0.dot(offset) / distance;
+
+                (distance < RANGE && closing_speed > 0.0).then_some((id, distance / closing_speed))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, _)| id);
+    }
+}
+
+fn aim_turrets(
+    mut commands: Commands,
+    mut turrets: Query<(&mut Turret, &mut Transform, &GlobalTransform2d, &ChildOf)>,
+    missiles: Query<(&Transform, &Velocity), Without<Turret>>,
+    gravity: Res<GravityField>,
+    time: Res<Time>,
+) {
+    for (mut turret, mut transform, global, child_of) in turrets.iter_mut() {
+        turret.cooldown = (turret.cooldown - time.delta_secs()).max(0.0);
+
+        let Some(missile) = turret.target.and_then(|target| missiles.get(target).ok()) else {
+            continue;
+        };
+
+        let origin = global.translation();
+        let Some(aim_point) = lead(origin, missile, &gravity, time.delta_secs()) else {
+            continue;
+        };
+
+        // turning the turret relative to the ship turns it by the same amount in the world
+        let target_heading = Rot2::radians((aim_point - origin).to_angle());
+        let error = global.rotation().angle_to(target_heading);
+        let max_turn = SLEW_RATE.to_radians() * time.delta_secs();
+        let turn = Rot2::radians(error.clamp(-max_turn, max_turn));
+        transform.rotation *= turn;
+
+        if error.abs() < FIRING_ARC.to_radians() && turret.cooldown == 0.0 {
+            turret.cooldown = COOLDOWN;
+
+            let heading = global.rotation() * turn;
+            commands.spawn((
+                Interceptor::default(),
+                Transform::default()
+                    .with_translation(origin)
+                    .with_rotation(heading),
+                Velocity(INTERCEPTOR_SPEED * (heading * Vec2::X)),
+                AlignToVelocity,
+                Owner(child_of.parent()),
+            ));
+        }
+    }
+}
+
+fn intercept(
+    mut commands: Commands,
+    interceptors: Query<(Entity, &Transform, &Owner), With<Interceptor>>,
+    missiles: Query<(Entity, &Transform, Option<&Owner>), With<Missile>>,
+) {
+    for (id, interceptor_transform, interceptor_owner) in interceptors.iter() {
+        let Some((missile, ..)) = missiles.iter().find(|(_, transform, owner)| {
+            owner.is_none_or(|owner| owner != interceptor_owner)
+                && transform
+                    .translation
+                    .distance(interceptor_transform.translation)
+                    < KILL_RADIUS
+        }) else {
+            continue;
+        };
+
+        commands.entity(missile).try_despawn();
+        commands.entity(id).despawn();
+    }
+}
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(crate::weapon::Plugin::<Interceptor>::default())
+            .add_systems(
+                FixedUpdate,
+                (acquire_targets, aim_turrets, intercept)
+                    .chain()
+                    .after(KinematicsSet),
+            )
+            .add_systems(PostUpdate, mount_turrets);
+
+        if crate::has_renderer(app) {
+            app.init_resource::<Sprites>()
+                .add_systems(PostUpdate, (spawn_turrets, spawn_interceptors));
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    KeyBinds, KeyPair, Ship, SimulationSettings, Star, Transform, Velocity, input::Inputs,
+    kinematics::OnRails,
+};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Every speed time can be warped to.
+pub const LEVELS: [f32; 7] = [1.0, 2.0, 4.0, 10.0, 50.0, 100.0, 1000.0];
+
+/// Levels below this are integrated as usual, just in more substeps, so ships can still thrust.
+/// From here on up everything coasts along Kepler orbits instead.
+const ON_RAILS_FROM: usize = 3;
+
+/// how much of the distance to the nearest star's surface a ship may cover in one tick
+const CLEARANCE: f32 = 0.25;
+
+/// How much faster than real time the simulation runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub struct TimeWarp {
+    level: usize,
+}
+
+impl TimeWarp {
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn factor(&self) -> f32 {
+        LEVELS[self.level]
+    }
+
+    pub fn is_on_rails(&self) -> bool {
+        self.level >= ON_RAILS_FROM
+    }
+
+    /// How much simulated time each fixed tick covers at this level.
+    pub fn timestep(&self, settings: &SimulationSettings) -> Duration {
+        settings.timestep().mul_f32(self.factor())
+    }
+
+    /// Sets the level, as long as it's one of [`LEVELS`].
+    pub fn set_level(&mut self, level: usize) {
+        self.level = level.min(LEVELS.len() - 1);
+    }
+}
+
+/// The highest level every ship can safely be warped at, going by how close it is to a star.
+fn safe_level(
+    ships: &Query<(&Transform, &Velocity), With<Ship>>,
+    stars: &Query<(&Star, &Transform)>,
+    timestep: Duration,
+) -> usize {
+    let mut level = LEVELS.len() - 1;
+
+    for (transform, velocity) in ships.iter() {
+        let clearance = stars
+            .iter()
+            .map(|(star, star_transform)| {
+                star_transform.translation.distance(transform.translation) - star.radius
+            })
+            .fold(f32::INFINITY, f32::min);
+
+        let per_tick = velocity.0.length() * timestep.as_secs_f32();
+        while level > 0 && per_tick * LEVELS[level] > CLEARANCE * clearance {
+            level -= 1;
+        }
+    }
+
+    level
+}
+
+fn change_warp(
+    mut warp: ResMut<TimeWarp>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<KeyBinds>,
+    ships: Query<(&Transform, &Velocity), With<Ship>>,
+    stars: Query<(&Star, &Transform)>,
+    settings: Res<SimulationSettings>,
+) {
+    if keys.any_just_pressed(keybinds.time_warp().map(KeyPair::less)) {
+        let level = warp.level.saturating_sub(1);
+        warp.set_if_neq(TimeWarp { level });
+    } else if keys.any_just_pressed(keybinds.time_warp().map(KeyPair::more)) {
+        let level = (warp.level + 1).min(safe_level(&ships, &stars, settings.timestep()));
+        warp.set_if_neq(TimeWarp { level });
+    }
+}
+
+/// Drops out of warp when a ship gets too close to a star, or tries to thrust while on rails.
+fn limit_warp(
+    mut warp: ResMut<TimeWarp>,
+    inputs: Res<Inputs>,
+    ships: Query<(&Transform, &Velocity), With<Ship>>,
+    stars: Query<(&Star, &Transform)>,
+    settings: Res<SimulationSettings>,
+) {
+    let mut level = warp
+        .level
+        .min(safe_level(&ships, &stars, settings.timestep()));
+
+    if warp.is_on_rails() && inputs.0.iter().any(|input| input.thrust) {
+        level = 0;
+    }
+
+    warp.set_if_neq(TimeWarp { level });
+}
+
+pub(crate) fn apply(
+    warp: Res<TimeWarp>,
+    settings: Res<SimulationSettings>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut fixed: ResMut<Time<Fixed>>,
+    mut on_rails: ResMut<OnRails>,
+) {
+    const MAX_DELTA: Duration = Duration::from_millis(250);
+
+    // ticks stay as frequent as ever in real time, they just each cover more of the simulation
+    virtual_time.set_relative_speed(warp.factor());
+    virtual_time.set_max_delta(MAX_DELTA.mul_f32(warp.factor()));
+    fixed.set_timestep(warp.timestep(&settings));
+    on_rails.0 = warp.is_on_rails();
+}
+
+#[derive(Clone, Copy, Debug, Component)]
+struct WarpIndicator;
+
+fn spawn_indicator(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        WarpIndicator,
+        Text::default(),
+        TextFont {
+            font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+            font_size: 24.0,
+            ..Default::default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(15.0),
+            ..Default::default()
+        },
+    ));
+}
+
+fn update_indicator(mut indicator: Single<&mut Text, With<WarpIndicator>>, warp: Res<TimeWarp>) {
+    indicator.0 = match warp.level {
+        0 => String::new(),
+        _ if warp.is_on_rails() => format!("×{} (on rails)", warp.factor()),
+        _ => format!("×{}", warp.factor()),
+    };
+}
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeWarp>()
+            .init_resource::<OnRails>()
+            .init_resource::<SimulationSettings>()
+            .init_resource::<Inputs>()
+            .add_systems(Update, (change_warp, limit_warp).chain())
+            .add_systems(
+                First,
+                apply
+                    .run_if(resource_changed::<TimeWarp>.or(resource_changed::<SimulationSettings>))
+                    .after(crate::settings::apply)
+                    .before(bevy::time::TimeSystem),
+            );
+
+        if crate::has_renderer(app) {
+            app.add_systems(Startup, spawn_indicator).add_systems(
+                PostUpdate,
+                update_indicator.run_if(resource_changed::<TimeWarp>),
+            );
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{SimulationSettings, kinematics::KinematicsSet};
+use bevy::{ecs::component::Mutable, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// Anything a ship can launch: missiles, mines, ...
+///
+/// Implementors get aging, fading and despawning for free by adding
+/// [`Plugin<Self>`](Plugin) to the app.
+pub trait Weapon: Component<Mutability = Mutable> + TypePath {
+    /// seconds until the projectile is despawned, unless
+    /// [`SimulationSettings::lifetimes`] says otherwise
+    const MAX_AGE: f32;
+
+    /// how many of these a single ship can have out at once
+    const MAX_PER_SHIP: usize = usize::MAX;
+
+    fn age(&self) -> f32;
+
+    fn age_mut(&mut self) -> &mut f32;
+
+    /// override for projectiles whose lifetime varies from one to the next
+    fn max_age(&self) -> f32 {
+        Self::MAX_AGE
+    }
+
+    fn color(&self) -> Color {
+        Color::WHITE
+    }
+}
+
+/// The ship that launched a projectile.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Owner(pub Entity);
+
+/// Sent whenever a weapon damages a ship.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Hit {
+    pub target: Entity,
+    pub damage: f32,
+}
+
+/// Whether `ship` is still under the [`Weapon::MAX_PER_SHIP`] cap for `W`.
+pub fn can_launch<W: Weapon>(ship: Entity, projectiles: &Query<&Owner, With<W>>) -> bool {
+    projectiles.iter().filter(|owner| owner.0 == ship).count() < W::MAX_PER_SHIP
+}
+
+fn update_age<W: Weapon>(mut projectiles: Query<&mut W>, time: Res<Time>) {
+    for mut projectile in projectiles.iter_mut() {
+        *projectile.age_mut() += time.delta_secs();
+    }
+}
+
+fn expire<W: Weapon>(
+    mut commands: Commands,
+    projectiles: Query<(Entity, &W)>,
+    settings: Res<SimulationSettings>,
+) {
+    for (id, projectile) in projectiles.iter() {
+        if projectile.age() > settings.max_age(projectile) {
+            commands.entity(id).despawn();
+        }
+    }
+}
+
+fn fade<W: Weapon>(
+    mut projectiles: Query<(&W, &mut MeshMaterial2d<ColorMaterial>)>,
+    mut colors: ResMut<Assets<ColorMaterial>>,
+    settings: Res<SimulationSettings>,
+) {
+    for (projectile, mut material) in projectiles.iter_mut() {
+        *material = MeshMaterial2d(
+            colors.add(
+                projectile
+                    .color()
+                    .with_alpha(1.0 - projectile.age() / settings.max_age(projectile)),
+            ),
+        );
+    }
+}
+
+pub struct Plugin<W>(PhantomData<W>);
+
+impl<W> Default for Plugin<W> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<W: Weapon> bevy::prelude::Plugin for Plugin<W> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Hit>()
+            .init_resource::<SimulationSettings>()
+            .add_systems(
+                FixedUpdate,
+                (update_age::<W>, expire::<W>).chain().before(KinematicsSet),
+            );
+
+        if crate::has_renderer(app) {
+            app.add_systems(PostUpdate, fade::<W>);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+#![allow(dead_code)]
+
+use bevy::{
+    app::PluginGroupBuilder,
+    ecs::query::QueryData,
+    input::{ButtonState, keyboard::KeyboardInput},
+    prelude::*,
+};
+use spacewar::{HeadlessPlugins, Scenario, Ship, Transform2d, Velocity, replay, settings};
+
+#[derive(Debug, Default, Resource)]
+struct Ticks(u32);
+
+/// A headless match that only moves when told to, one fixed tick per step.
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    pub fn new(scenario: Scenario) -> Self {
+        Self::with_replay(scenario, replay::Mode::Off)
+    }
+
+    /// A match that's recorded, or played back from a replay, depending on `mode`.
+    pub fn with_replay(scenario: Scenario, mode: replay::Mode) -> Self {
+        Self::with_plugins(scenario, |plugins| {
+            plugins.set(replay::Plugin {
+                mode,
+                ..Default::default()
+            })
+        })
+    }
+
+    /// A match with the plugins changed by `configure` before it starts.
+    pub fn with_plugins(
+        scenario: Scenario,
+        configure: impl FnOnce(PluginGroupBuilder) -> PluginGroupBuilder,
+    ) -> Self {
+        let mut app = App::new();
+        app.add_plugins(configure(
+            HeadlessPlugins {
+                scenario,
+                ..Default::default()
+            }
+            .build()
+            // tests shouldn't depend on whatever's been tuned in the settings file
+            .set(settings::Plugin {
+                path: None,
+                ..Default::default()
+            }),
+        ));
+        app.finish();
+        app.cleanup();
+
+        app.init_resource::<Ticks>()
+            .add_systems(FixedFirst, |mut ticks: ResMut<Ticks>| ticks.0 += 1);
+
+        // runs the startup schedules, but doesn't tick yet since the clock only starts here
+        app.update();
+        assert_eq!(app.world().resource::<Ticks>().0, 0);
+
+        Self { app }
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    /// Runs `ticks` fixed ticks, with input handled once before each of them.
+    pub fn tick(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            let before = self.elapsed_ticks();
+            self.app.update();
+            assert_eq!(
+                self.elapsed_ticks(),
+                before + 1,
+                "update didn't run exactly one tick"
+            );
+        }
+    }
+
+    /// Runs a single frame, however many ticks that turns out to be.
+    pub fn frame(&mut self) {
+        self.app.update();
+    }
+
+    pub fn elapsed_ticks(&self) -> u32 {
+        self.app.world().resource::<Ticks>().0
+    }
+
+    /// Holds `key` down from the next tick on, as if it came from a keyboard.
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    /// Presses `key` for exactly one tick.
+    pub fn tap(&mut self, key: KeyCode) {
+        self.press(key);
+        self.tick(1);
+        self.release(key);
+    }
+
+    fn send_key(&mut self, key_code: KeyCode, state: ButtonState) {
+        self.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key: bevy::input::keyboard::Key::Unidentified(
+                bevy::input::keyboard::NativeKey::Unidentified,
+            ),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    /// The only entity matching `D`, panicking if there isn't exactly one.
+    pub fn single<D: QueryData>(&mut self) -> D::Item<'_> {
+        let world = self.app.world_mut();
+        let mut query = world.query::<D>();
+        query.single_mut(world).unwrap()
+    }
+
+    pub fn count<D: QueryData>(&mut self) -> usize {
+        let world = self.app.world_mut();
+        world.query::<D>().iter(world).count()
+    }
+
+    pub fn ship(&mut self) -> (Ship, Transform2d, Velocity) {
+        let (ship, transform, velocity) = self.single::<(&Ship, &Transform2d, &Velocity)>();
+        (ship.clone(), *transform, *velocity)
+    }
+}
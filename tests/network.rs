@@ -0,0 +1,162 @@
+mod common;
+
+use bevy::prelude::*;
+use common::Harness;
+use spacewar::{
+    Missile, Scenario,
+    kinematics::SimulationTick,
+    net::{self, NetSettings, Role, Session},
+    rewind::History,
+    scenario::{Body, Placement},
+};
+use std::net::{Ipv4Addr, SocketAddr};
+
+fn scenario(mass: f32) -> Scenario {
+    Scenario {
+        bodies: vec![Body {
+            name: "star".into(),
+            mass,
+            radius: 10.0,
+            placement: Placement::Fixed(Vec2::ZERO),
+        }],
+        ..Default::default()
+    }
+}
+
+/// A host and someone joining it over loopback, both with `settings`.
+fn connect(host: Scenario, join: Scenario, settings: NetSettings) -> (Harness, Harness) {
+    let session = Session::new(
+        Role::Host(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))),
+        settings,
+    )
+    .unwrap();
+    let address = session.local_addr().unwrap();
+    let host = Harness::with_plugins(host, |plugins| plugins.add(net::Plugin::new(session)));
+
+    let session = Session::new(Role::Join(address), settings).unwrap();
+    let join = Harness::with_plugins(join, |plugins| plugins.add(net::Plugin::new(session)));
+
+    (host, join)
+}
+
+fn frames(host: &mut Harness, join: &mut Harness, frames: u32) {
+    for _ in 0..frames {
+        host.frame();
+        join.frame();
+    }
+}
+
+fn tick(harness: &Harness) -> u64 {
+    harness.app.world().resource::<SimulationTick>().0
+}
+
+fn session(harness: &Harness) -> &Session {
+    harness.app.world().resource::<Session>()
+}
+
+/// Checksums of both sides at the latest tick neither can roll back past any more.
+fn settled_checksums(host: &Harness, join: &Harness) -> (u64, u64, u64) {
+    let settled = [
+        tick(host),
+        tick(join),
+        session(host).confirmed(),
+        session(join).confirmed(),
+    ]
+    .into_iter()
+    .min()
+    .unwrap();
+
+    let checksum = |harness: &Harness| {
+        harness
+            .app
+            .world()
+            .resource::<History>()
+            .get(settled)
+            .unwrap()
+            .checksum()
+    };
+    (settled, checksum(host), checksum(join))
+}
+
+/// Thrusting on one side and turning and firing on the other, over `settings`.
+fn play(settings: NetSettings) -> (Harness, Harness) {
+    let (mut host, mut join) = connect(scenario(1.6e16), scenario(1.6e16), settings);
+    frames(&mut host, &mut join, 10);
+
+    host.press(KeyCode::KeyW);
+    join.press(KeyCode::KeyA);
+    frames(&mut host, &mut join, 40);
+    host.release(KeyCode::KeyW);
+    join.release(KeyCode::KeyA);
+
+    join.press(KeyCode::Space);
+    frames(&mut host, &mut join, 1);
+    join.release(KeyCode::Space);
+    frames(&mut host, &mut join, 20);
+
+    (host, join)
+}
+
+#[test]
+fn peers_stay_in_sync() {
+    let (mut host, mut join) = play(NetSettings::default());
+
+    assert_eq!(host.count::<&Missile>(), 1);
+    assert_eq!(join.count::<&Missile>(), 1);
+
+    frames(&mut host, &mut join, 200);
+    assert!(tick(&host) > 250, "only got to tick {}", tick(&host));
+    assert!(tick(&join) > 250, "only got to tick {}", tick(&join));
+
+    let (settled, ours, theirs) = settled_checksums(&host, &join);
+    assert_eq!(ours, theirs, "differ at tick {settled}");
+    assert_eq!(session(&host).desync, None);
+    assert_eq!(session(&join).desync, None);
+}
+
+#[test]
+fn wrong_guesses_are_rolled_back() {
+    // with no delay at all, the joiner's input always arrives after the host needed it
+    let (mut host, mut join) = play(NetSettings {
+        input_delay: 0,
+        ..Default::default()
+    });
+    frames(&mut host, &mut join, 10);
+
+    assert!(session(&host).rollbacks > 0);
+    assert_eq!(host.count::<&Missile>(), 1);
+    assert_eq!(join.count::<&Missile>(), 1);
+
+    let (settled, ours, theirs) = settled_checksums(&host, &join);
+    assert_eq!(ours, theirs, "differ at tick {settled}");
+    assert_eq!(session(&host).desync, None);
+    assert_eq!(session(&join).desync, None);
+}
+
+#[test]
+fn waits_for_the_other_side() {
+    let settings = NetSettings::default();
+    let (mut host, mut join) = connect(scenario(1.6e16), scenario(1.6e16), settings);
+    frames(&mut host, &mut join, 20);
+
+    for _ in 0..50 {
+        host.frame();
+    }
+
+    // the joiner's input went out `input_delay` ticks ahead
+    let limit = tick(&join) + settings.input_delay + settings.max_prediction;
+    assert_eq!(tick(&host), limit);
+
+    frames(&mut host, &mut join, 20);
+    assert!(tick(&host) > limit);
+    assert_eq!(session(&host).desync, None);
+}
+
+#[test]
+fn differing_worlds_are_noticed() {
+    let (mut host, mut join) = connect(scenario(1.6e16), scenario(3.2e16), NetSettings::default());
+    frames(&mut host, &mut join, 100);
+
+    assert!(session(&host).desync.is_some());
+    assert!(session(&join).desync.is_some());
+}
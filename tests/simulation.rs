@@ -0,0 +1,695 @@
+mod common;
+
+use bevy::{diagnostic::DiagnosticsStore, prelude::*};
+use common::Harness;
+use spacewar::{
+    Missile, Replay, SaveGame, Scenario, Ship, SimulationSettings, Star, TimeWarp, Transform2d,
+    Velocity, conservation,
+    generator::{self, Generator},
+    gravity::GRAVITATIONAL_CONSTANT,
+    kinematics::SimulationTick,
+    pause,
+    replay::{self, Playback, Recording},
+    rewind::{History, Snapshot},
+    save,
+    scenario::{self, Body, Placement, Spawn},
+    settings,
+    ship::{Health, SASMode},
+    star,
+    transform2d::PreviousTransform2d,
+    warp,
+    weapon::Owner,
+};
+
+fn single_star(mass: f32) -> Scenario {
+    Scenario {
+        bodies: vec![Body {
+            name: "star".into(),
+            mass,
+            radius: 10.0,
+            placement: Placement::Fixed(Vec2::ZERO),
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn circular_orbit_stays_circular() {
+    const MASS: f32 = 1.6e18;
+    const RADIUS: f32 = 1000.0;
+    const ORBITS: f32 = 10.0;
+
+    let speed = (GRAVITATIONAL_CONSTANT * MASS / RADIUS).sqrt();
+    let period = std::f32::consts::TAU * RADIUS / speed;
+
+    let mut harness = Harness::new(Scenario {
+        spawns: vec![Spawn {
+            position: Vec2::new(RADIUS, 0.0),
+            velocity: Vec2::new(0.0, speed),
+        }],
+        ..single_star(MASS)
+    });
+
+    let ticks = (ORBITS * period / Time::<Fixed>::default().timestep().as_secs_f32()) as u32;
+    for _ in 0..ticks {
+        harness.tick(1);
+
+        let (_, transform, _) = harness.ship();
+        let radius = transform.translation.length();
+        assert!(
+            (radius - RADIUS).abs() < RADIUS * 0.01,
+            "drifted to {radius} after {} ticks",
+            harness.elapsed_ticks()
+        );
+    }
+}
+
+#[test]
+fn prograde_sas_aligns_heading() {
+    let mut harness = Harness::new(single_star(1.6e16));
+    harness.tick(1);
+
+    // respawned ships come with SAS off
+    harness.tap(KeyCode::KeyT);
+    harness.tap(KeyCode::KeyP);
+    assert_eq!(harness.ship().0.sas, Some(SASMode::Prograde));
+
+    harness.tick(64 * 20);
+
+    let (_, transform, velocity) = harness.ship();
+    let error = transform.local_x().angle_to(velocity.0).to_degrees();
+    assert!(error.abs() < 5.0, "heading is {error} degrees off prograde");
+
+    // turning all the way round, without swinging past
+    harness.single::<&mut Ship>().sas = Some(SASMode::Retrograde);
+    harness.tick(64 * 5);
+    let (_, transform, velocity) = harness.ship();
+    let error = transform.local_x().angle_to(-velocity.0).to_degrees();
+    assert!(
+        error.abs() < 5.0,
+        "heading is {error} degrees off retrograde"
+    );
+}
+
+/// How much faster the ship goes, then how fast it's spinning, after a second of simulated time
+/// holding down thrust and then a second holding down turn, out in empty space.
+fn handling(harness: &mut Harness) -> (f32, f32) {
+    let hold = |harness: &mut Harness, key| {
+        let start = harness.world_mut().resource::<Time<Fixed>>().elapsed_secs();
+        harness.press(key);
+        while harness.world_mut().resource::<Time<Fixed>>().elapsed_secs() - start < 0.999 {
+            harness.tick(1);
+        }
+        harness.release(key);
+    };
+
+    hold(harness, KeyCode::KeyW);
+    let speed = harness.ship().2.0.length();
+    hold(harness, KeyCode::KeyA);
+    (speed, harness.ship().0.rotational_velocity)
+}
+
+#[test]
+fn handling_doesnt_depend_on_tick_rate_or_warp() {
+    let empty = Scenario {
+        bodies: Vec::new(),
+        spawns: vec![Spawn {
+            position: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+        }],
+        ..Default::default()
+    };
+
+    let mut harness = Harness::new(empty.clone());
+    let (speed, spin) = handling(&mut harness);
+    assert!(
+        (speed - SimulationSettings::default().thrust).abs() < 1e-3,
+        "{speed}"
+    );
+
+    let mut slow = Harness::with_plugins(empty.clone(), |plugins| {
+        plugins.set(settings::Plugin {
+            path: None,
+            tick_rate: Some(32.0),
+            ..Default::default()
+        })
+    });
+    let mut warped = Harness::new(empty);
+    warped.tap(KeyCode::Equal);
+    warped.tap(KeyCode::Equal);
+    assert_eq!(warped.world_mut().resource::<TimeWarp>().factor(), 4.0);
+
+    for harness in [&mut slow, &mut warped] {
+        let (other_speed, other_spin) = handling(harness);
+        assert!(
+            (other_speed - speed).abs() < 1e-3,
+            "{other_speed} != {speed}"
+        );
+        assert!(
+            (other_spin - spin).abs() < spin.abs() * 0.05,
+            "{other_spin} != {spin}"
+        );
+    }
+}
+
+#[test]
+fn respawn_resets_ship() {
+    let scenario = single_star(1.6e16);
+    let mut harness = Harness::new(scenario.clone());
+    harness.tick(1);
+
+    harness.press(KeyCode::KeyW);
+    harness.press(KeyCode::KeyA);
+    harness.tick(64);
+    harness.release(KeyCode::KeyW);
+    harness.release(KeyCode::KeyA);
+    harness.single::<&mut Health>().0 = 10.0;
+
+    harness.tap(KeyCode::KeyR);
+
+    assert_eq!(harness.count::<&Ship>(), 1);
+    let (ship, transform, velocity) = harness.ship();
+    assert_eq!(transform.translation, scenario.spawns[0].position);
+    assert_eq!(transform.rotation, Rot2::IDENTITY);
+    assert_eq!(velocity, Velocity(scenario.spawns[0].velocity));
+    assert_eq!(ship.rotational_velocity, 0.0);
+    assert_eq!(harness.single::<&Health>().0, Health::MAX);
+}
+
+#[test]
+fn eccentric_orbit_conserves_energy() {
+    const MASS: f32 = 1.6e18;
+    const START: Vec2 = Vec2::new(1000.0, 0.0);
+    const SPEED: f32 = 200.0;
+
+    // slower than circular, so it falls in to about a quarter of the distance and back
+    let mut harness = Harness::new(Scenario {
+        spawns: vec![Spawn {
+            position: START,
+            velocity: Vec2::new(0.0, SPEED),
+        }],
+        ..single_star(MASS)
+    });
+
+    let gm = GRAVITATIONAL_CONSTANT * MASS;
+    let semi_major_axis = 1.0 / (2.0 / START.length() - SPEED.powi(2) / gm);
+    let period = std::f32::consts::TAU * (semi_major_axis.powi(3) / gm).sqrt();
+    let ticks_per_orbit = (period / Time::<Fixed>::default().timestep().as_secs_f32()) as u32;
+
+    // back at the start, where any energy borrowed around periapsis should have been returned
+    harness.tick(ticks_per_orbit * 10);
+
+    let diagnostics = harness.world_mut().resource::<DiagnosticsStore>();
+    let drift = |path| diagnostics.get(&path).and_then(|d| d.value()).unwrap();
+
+    let energy = drift(conservation::ENERGY_DRIFT);
+    assert!(energy < 0.5, "energy drifted by {energy}%");
+
+    let angular_momentum = drift(conservation::ANGULAR_MOMENTUM_DRIFT);
+    assert!(
+        angular_momentum < 0.01,
+        "angular momentum drifted by {angular_momentum}%"
+    );
+}
+
+#[test]
+fn warp_drops_near_stars() {
+    const MASS: f32 = 1.6e18;
+    const RADIUS: f32 = 1000.0;
+
+    let mut harness = Harness::new(Scenario {
+        spawns: vec![Spawn {
+            position: Vec2::new(RADIUS, 0.0),
+            velocity: Vec2::new(0.0, (GRAVITATIONAL_CONSTANT * MASS / RADIUS).sqrt()),
+        }],
+        ..single_star(MASS)
+    });
+
+    for _ in warp::LEVELS {
+        harness.tap(KeyCode::Equal);
+    }
+    let level = harness.world_mut().resource::<TimeWarp>().level();
+    assert!(
+        level > 0 && level < warp::LEVELS.len() - 1,
+        "warped at level {level}"
+    );
+}
+
+#[test]
+fn warp_on_rails_keeps_orbit() {
+    const MASS: f32 = 1.6e18;
+    const RADIUS: f32 = 100_000.0;
+
+    let speed = (GRAVITATIONAL_CONSTANT * MASS / RADIUS).sqrt();
+    let period = std::f32::consts::TAU * RADIUS / speed;
+
+    let mut harness = Harness::new(Scenario {
+        spawns: vec![Spawn {
+            position: Vec2::new(RADIUS, 0.0),
+            velocity: Vec2::new(0.0, speed),
+        }],
+        ..single_star(MASS)
+    });
+
+    for _ in warp::LEVELS {
+        harness.tap(KeyCode::Equal);
+    }
+    let warp = *harness.world_mut().resource::<TimeWarp>();
+    assert_eq!(warp.factor(), 1000.0);
+    assert!(warp.is_on_rails());
+
+    let start = harness.world_mut().resource::<Time<Fixed>>().elapsed_secs();
+    while harness.world_mut().resource::<Time<Fixed>>().elapsed_secs() - start < period {
+        harness.tick(1);
+    }
+    assert!(harness.elapsed_ticks() < 2000);
+
+    let (_, transform, _) = harness.ship();
+    let radius = transform.translation.length();
+    assert!(
+        (radius - RADIUS).abs() < RADIUS * 1e-3,
+        "drifted to {radius}"
+    );
+
+    // thrusting knocks it right back out of warp
+    harness.tap(KeyCode::KeyW);
+    assert_eq!(harness.world_mut().resource::<TimeWarp>().factor(), 1.0);
+}
+
+#[test]
+fn paused_only_moves_when_stepped() {
+    let mut harness = Harness::new(single_star(1.6e16));
+    harness.tick(1);
+
+    harness.tap(KeyCode::Backquote);
+    let (_, frozen, _) = harness.ship();
+    let ticks = harness.elapsed_ticks();
+
+    for _ in 0..10 {
+        harness.frame();
+    }
+    assert_eq!(harness.elapsed_ticks(), ticks);
+    assert_eq!(harness.ship().1, frozen);
+
+    harness.press(KeyCode::KeyN);
+    harness.frame();
+    harness.release(KeyCode::KeyN);
+    harness.frame();
+    assert_eq!(harness.elapsed_ticks(), ticks + 1);
+    assert_ne!(harness.ship().1, frozen);
+
+    harness.press(KeyCode::ShiftLeft);
+    harness.press(KeyCode::KeyN);
+    harness.frame();
+    harness.release(KeyCode::KeyN);
+    harness.release(KeyCode::ShiftLeft);
+    harness.frame();
+    assert_eq!(harness.elapsed_ticks(), ticks + 1 + pause::MANY_STEPS);
+
+    // unpausing takes effect from the frame after
+    harness.press(KeyCode::Backquote);
+    harness.frame();
+    harness.release(KeyCode::Backquote);
+    harness.tick(1);
+}
+
+fn rewind_to(harness: &mut Harness, tick: u64) {
+    let rewound = harness
+        .world_mut()
+        .resource_scope(|world, mut history: Mut<History>| history.rewind_to(tick, world));
+    assert!(rewound, "tick {tick} isn't in the history");
+}
+
+#[test]
+fn rewind_replays_identically() {
+    let mut harness = Harness::new(single_star(1.6e16));
+    harness.tick(64);
+
+    let tick = harness.world_mut().resource::<SimulationTick>().0;
+    let (_, before, _) = harness.ship();
+
+    harness.tap(KeyCode::Space);
+    harness.tick(63);
+    let later = harness
+        .world_mut()
+        .resource::<History>()
+        .get(tick + 10)
+        .unwrap()
+        .clone();
+    assert_eq!(harness.count::<&Missile>(), 1);
+
+    rewind_to(&mut harness, tick);
+    assert_eq!(harness.ship().1, before);
+    assert_eq!(harness.count::<&Missile>(), 0);
+    // rolling back isn't a jump, rendering still interpolates from where it last was
+    assert_eq!(harness.count::<(&Ship, &PreviousTransform2d)>(), 1);
+
+    // with the same inputs, the same thing happens all over again
+    harness.tap(KeyCode::Space);
+    harness.tick(9);
+    let replayed = Snapshot::capture(harness.world_mut());
+    assert_eq!(replayed.tick, later.tick);
+    assert_eq!(replayed.bodies.len(), later.bodies.len());
+    for body in &later.bodies {
+        let other = replayed
+            .bodies
+            .iter()
+            .find(|other| other.ship.is_some() == body.ship.is_some())
+            .unwrap();
+        assert_eq!(other.transform, body.transform);
+        assert_eq!(other.velocity, body.velocity);
+    }
+}
+
+#[test]
+fn rewind_respawns_despawned_bodies() {
+    let mut harness = Harness::new(single_star(1.6e16));
+    harness.tick(1);
+    harness.tap(KeyCode::Space);
+    // launched after this tick's snapshot, so it shows up in the next one
+    harness.tick(1);
+    let tick = harness.world_mut().resource::<SimulationTick>().0;
+
+    let ship = harness.single::<(Entity, &Ship)>().0;
+    let missile = harness.single::<(Entity, &Missile)>().0;
+    harness.world_mut().despawn(missile);
+    harness.tick(10);
+    assert_eq!(harness.count::<&Missile>(), 0);
+
+    rewind_to(&mut harness, tick);
+    let owner = *harness.single::<(&Missile, &Owner)>().1;
+    assert_eq!(owner, Owner(ship));
+}
+
+#[test]
+fn scenario_file_stars_follow_their_orbits() {
+    let mut harness = Harness::with_plugins(Scenario::default(), |plugins| {
+        plugins.set(scenario::Plugin {
+            path: Some("scenarios/binary.scenario.ron".into()),
+            ..Default::default()
+        })
+    });
+
+    // nothing ticks until the file has loaded
+    for _ in 0..1000 {
+        if harness.count::<&Star>() > 0 {
+            break;
+        }
+        harness.frame();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    assert_eq!(harness.count::<&Star>(), 2);
+    assert_eq!(harness.count::<&Ship>(), 1);
+
+    let companion = |harness: &mut Harness| {
+        harness
+            .single::<(&Transform2d, &star::Orbiting)>()
+            .0
+            .translation
+    };
+    harness.tick(1);
+    let start = companion(&mut harness);
+    let tick = harness.world_mut().resource::<SimulationTick>().0;
+
+    for _ in 0..20 {
+        harness.tick(64);
+        // 2500 out, with an eccentricity of 0.2
+        let distance = companion(&mut harness).length();
+        assert!((1990.0..=3010.0).contains(&distance), "{distance} out");
+    }
+    assert!(companion(&mut harness).distance(start) > 100.0);
+
+    // the stars catch up on the next frame, ticking or not
+    rewind_to(&mut harness, tick);
+    harness.world_mut().resource_mut::<Time<Virtual>>().pause();
+    harness.frame();
+    assert_eq!(companion(&mut harness), start);
+}
+
+#[test]
+fn scrubbing_pauses_and_resumes_from_there() {
+    let mut harness = Harness::new(single_star(1.6e16));
+    harness.tick(64);
+    let latest = harness.world_mut().resource::<SimulationTick>().0;
+
+    harness.press(KeyCode::KeyZ);
+    for _ in 0..10 {
+        harness.frame();
+    }
+    harness.release(KeyCode::KeyZ);
+    harness.frame();
+
+    assert!(harness.world_mut().resource::<Time<Virtual>>().is_paused());
+    let tick = harness.world_mut().resource::<SimulationTick>().0;
+    assert!(tick < latest && tick >= latest - 11, "scrubbed to {tick}");
+    assert_eq!(harness.count::<(&Ship, &PreviousTransform2d)>(), 0);
+
+    harness.press(KeyCode::Backquote);
+    harness.frame();
+    harness.release(KeyCode::Backquote);
+    harness.tick(1);
+    assert_eq!(harness.world_mut().resource::<SimulationTick>().0, tick + 1);
+    let history = harness.world_mut().resource::<History>();
+    assert_eq!(history.snapshots().last().unwrap().tick, tick + 1);
+}
+
+#[test]
+fn history_stays_within_memory_limit() {
+    let mut harness = Harness::new(single_star(1.6e16));
+    let limit = 100 * size_of::<Snapshot>();
+    harness
+        .world_mut()
+        .resource_mut::<SimulationSettings>()
+        .rewind_memory = limit;
+
+    harness.tick(500);
+    let history = harness.world_mut().resource::<History>();
+    assert!(history.size() <= limit);
+    assert!(history.len() > 1 && history.len() < 100);
+}
+
+/// A short match with a bit of everything in it, recorded, and a checksum of how it ended.
+fn record_match() -> (Replay, u64) {
+    let scenario = Scenario {
+        seed: 7,
+        ..single_star(1.6e16)
+    };
+    let mut harness = Harness::with_replay(scenario, replay::Mode::Record(None));
+
+    harness.press(KeyCode::KeyW);
+    harness.press(KeyCode::KeyA);
+    harness.tick(40);
+    harness.release(KeyCode::KeyW);
+    harness.release(KeyCode::KeyA);
+
+    harness.tap(KeyCode::Space);
+    harness.tick(20);
+    harness.tap(KeyCode::KeyM);
+    harness.tick(20);
+    harness.tap(KeyCode::Equal);
+    harness.tick(100);
+    harness.tap(KeyCode::KeyR);
+    harness.tick(30);
+
+    let checksum = Snapshot::capture(harness.world_mut()).checksum();
+    let recording = harness.world_mut().remove_resource::<Recording>().unwrap();
+    (recording.replay, checksum)
+}
+
+#[test]
+fn generated_systems_start_ships_in_a_safe_orbit() {
+    for seed in 0..4 {
+        let scenario = Generator::default().generate(seed);
+        let radius = scenario.spawns[0].position.length();
+        let mut harness = Harness::new(scenario);
+        harness.tick(640);
+
+        assert_eq!(harness.single::<&Health>().0, 100.0, "seed {seed}");
+        let distance = harness.ship().1.translation.length();
+        assert!(
+            (distance - radius).abs() < radius * generator::SPAWN_BAND,
+            "seed {seed}: drifted from {radius} to {distance}"
+        );
+    }
+}
+
+#[test]
+fn saved_match_carries_on_identically() {
+    let mut harness = Harness::new(Scenario {
+        bodies: vec![
+            Body {
+                name: "primary".into(),
+                mass: 1.6e16,
+                radius: 10.0,
+                placement: Placement::Fixed(Vec2::ZERO),
+            },
+            Body {
+                name: "companion".into(),
+                mass: 4.0e15,
+                radius: 5.0,
+                placement: Placement::Elements {
+                    around: "primary".into(),
+                    semi_major_axis: 2500.0,
+                    eccentricity: 0.2,
+                    argument_of_periapsis: 0.0,
+                    true_anomaly: 0.0,
+                    prograde: true,
+                },
+            },
+        ],
+        seed: 7,
+        ..Default::default()
+    });
+    harness.press(KeyCode::KeyW);
+    harness.tick(32);
+    harness.release(KeyCode::KeyW);
+    harness.tap(KeyCode::KeyT);
+    harness.tap(KeyCode::Space);
+    harness.tick(16);
+
+    let path = std::env::temp_dir().join(format!("spacewar-save-{}.ron", std::process::id()));
+    SaveGame::capture(harness.world_mut()).save(&path).unwrap();
+    let sas = harness.ship().0.sas;
+    harness.tick(200);
+    let expected = Snapshot::capture(harness.world_mut());
+
+    // starting out from a different match altogether
+    let save = SaveGame::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut loaded = Harness::with_plugins(single_star(3.2e16), |plugins| {
+        plugins.set(save::Plugin {
+            load: Some(Box::new(save)),
+            ..Default::default()
+        })
+    });
+    assert_eq!(loaded.count::<&Star>(), 2);
+    assert_eq!(loaded.count::<&Missile>(), 1);
+    assert_eq!(loaded.ship().0.sas, sas);
+
+    loaded.tick(200);
+    let actual = Snapshot::capture(loaded.world_mut());
+    assert_eq!(actual.tick, expected.tick);
+    // bodies come out of a loaded world in whatever order they were respawned in
+    assert_eq!(body_checksums(&actual), body_checksums(&expected));
+}
+
+fn body_checksums(snapshot: &Snapshot) -> Vec<u64> {
+    let mut checksums: Vec<u64> = snapshot
+        .bodies
+        .iter()
+        .map(|body| {
+            Snapshot {
+                bodies: vec![body.clone()],
+                ..Default::default()
+            }
+            .checksum()
+        })
+        .collect();
+    checksums.sort_unstable();
+    checksums
+}
+
+#[test]
+fn replay_plays_back_identically() {
+    let (replay, checksum) = record_match();
+    let ticks = replay.len();
+    assert_eq!(ticks, 214);
+    assert_eq!(
+        replay.checksums.len() as u64,
+        ticks / replay::CHECK_INTERVAL
+    );
+    assert!(replay.ticks.iter().any(|tick| tick.warp > 0));
+
+    // the scenario comes from the replay, not whatever the match was started with
+    let mut harness = Harness::with_replay(
+        Scenario {
+            bodies: Vec::new(),
+            ..Default::default()
+        },
+        replay::Mode::Play(Box::new(replay)),
+    );
+    harness.tick(ticks as u32);
+
+    let playback = harness.world_mut().resource::<Playback>();
+    assert_eq!(playback.desync, None);
+    assert!(playback.is_finished(SimulationTick(ticks)));
+    assert_eq!(harness.count::<&Star>(), 1);
+    assert_eq!(Snapshot::capture(harness.world_mut()).checksum(), checksum);
+}
+
+#[test]
+fn replay_notices_desync() {
+    let (mut replay, _) = record_match();
+    replay.ticks[99].inputs[0].thrust = true;
+    let first_check = *replay.checksums.keys().find(|tick| **tick >= 100).unwrap();
+    let ticks = replay.len();
+
+    let mut harness =
+        Harness::with_replay(Scenario::default(), replay::Mode::Play(Box::new(replay)));
+    harness.tick(ticks as u32);
+
+    let desync = harness.world_mut().resource::<Playback>().desync.unwrap();
+    assert_eq!(desync.tick, first_check);
+    assert_ne!(desync.expected, desync.actual);
+}
+
+/// Changes to anything in the simulation are expected to change this, in which case it needs
+/// updating, but nothing else should: not the platform, the compiler version or the optimization
+/// level.
+#[cfg(feature = "deterministic")]
+const GOLDEN_CHECKSUM: u64 = 0xb9bb_295e_9e3d_0457;
+
+#[cfg(feature = "deterministic")]
+#[test]
+fn long_match_matches_golden_checksum() {
+    let mut harness = Harness::new(Scenario {
+        bodies: vec![
+            Body {
+                name: "primary".into(),
+                mass: 1.6e16,
+                radius: 10.0,
+                placement: Placement::Fixed(Vec2::ZERO),
+            },
+            Body {
+                name: "secondary".into(),
+                mass: 4.0e15,
+                radius: 5.0,
+                placement: Placement::Fixed(Vec2::new(2500.0, -400.0)),
+            },
+        ],
+        seed: 44,
+        ..Default::default()
+    });
+
+    // a bit of thrusting, turning and shooting every so often, with a stretch on rails
+    for round in 0..10 {
+        harness.press(KeyCode::KeyW);
+        harness.press(KeyCode::KeyA);
+        harness.tick(30);
+        harness.release(KeyCode::KeyW);
+        harness.release(KeyCode::KeyA);
+
+        harness.tap(KeyCode::Space);
+        harness.tap(KeyCode::KeyM);
+        harness.tap(KeyCode::KeyF);
+
+        if round == 5 {
+            for _ in 0..warp::LEVELS.len() {
+                harness.tap(KeyCode::Equal);
+            }
+        }
+        harness.tick(497);
+        if round == 5 {
+            for _ in 0..warp::LEVELS.len() {
+                harness.tap(KeyCode::Minus);
+            }
+        }
+    }
+    harness.tick(10_000 - harness.elapsed_ticks());
+
+    let checksum = Snapshot::capture(harness.world_mut()).checksum();
+    assert_eq!(
+        checksum, GOLDEN_CHECKSUM,
+        "got {checksum:#x}, only update the golden checksum if the simulation itself changed"
+    );
+}